@@ -0,0 +1,21 @@
+// Declares the C functions of the ffi feature in connect4.h under OUT_DIR. The copy kept with the
+// sources in include/ is for programs built without cargo, and a test checks it is up to date.
+// After changing src/ffi.rs, refresh it with:
+//   cbindgen --config cbindgen.toml --output include/connect4.h
+fn main() {
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        let directory = std::env::var("CARGO_MANIFEST_DIR").expect("Cargo gives the directory");
+        let out = std::env::var("OUT_DIR").expect("Cargo gives the output directory");
+        let config = cbindgen::Config::from_file(format!("{directory}/cbindgen.toml"))
+            .expect("cbindgen.toml is valid");
+        cbindgen::Builder::new()
+            .with_crate(&directory)
+            .with_config(config)
+            .generate()
+            .expect("The functions can be declared in C")
+            .write_to_file(format!("{out}/connect4.h"));
+    }
+}
@@ -0,0 +1,63 @@
+#ifndef CONNECT4_H
+#define CONNECT4_H
+
+/* Generated from src/ffi.rs with cbindgen --config cbindgen.toml, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum Connect4Result {
+  CONNECT4_RESULT_OK = 0,
+  CONNECT4_RESULT_OUT_OF_BOUNDS = 1,
+  CONNECT4_RESULT_COLUMN_FULL = 2,
+  CONNECT4_RESULT_OVER = 3,
+  CONNECT4_RESULT_NO_GAME = 4,
+  CONNECT4_RESULT_CANNOT_POP = 5,
+  CONNECT4_RESULT_FORBIDDEN = 6,
+  CONNECT4_RESULT_OCCUPIED = 7,
+} Connect4Result;
+
+typedef enum Connect4Status {
+  CONNECT4_STATUS_ONGOING = 0,
+  CONNECT4_STATUS_WHITE_WON = 1,
+  CONNECT4_STATUS_BLACK_WON = 2,
+  CONNECT4_STATUS_TIE = 3,
+} Connect4Status;
+
+typedef enum Connect4Token {
+  CONNECT4_TOKEN_EMPTY = 0,
+  CONNECT4_TOKEN_WHITE = 1,
+  CONNECT4_TOKEN_BLACK = 2,
+} Connect4Token;
+
+typedef struct Connect4Game Connect4Game;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+struct Connect4Game *connect4_new(uint8_t width, uint8_t height, uint8_t win);
+
+void connect4_free(struct Connect4Game *game);
+
+enum Connect4Result connect4_place(struct Connect4Game *game, uint8_t column);
+
+enum Connect4Status connect4_status(const struct Connect4Game *game);
+
+enum Connect4Token connect4_turn(const struct Connect4Game *game);
+
+enum Connect4Token connect4_cell(const struct Connect4Game *game, uint8_t x, uint8_t y);
+
+uint8_t connect4_width(const struct Connect4Game *game);
+
+uint8_t connect4_height(const struct Connect4Game *game);
+
+int16_t connect4_best_move(const struct Connect4Game *game, uint8_t depth, uint32_t milliseconds);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CONNECT4_H */
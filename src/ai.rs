@@ -0,0 +1,817 @@
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Level {
+    Beginner,
+    Casual,
+    Club,
+    Expert,
+    Perfect,
+}
+
+impl Level {
+    fn depth(self) -> u8 {
+        match self {
+            Self::Beginner => 2,
+            Self::Casual => 4,
+            Self::Club => 6,
+            Self::Expert => 8,
+            Self::Perfect => 9,
+        }
+    }
+
+    fn mistakes(self) -> Mistakes {
+        match self {
+            Self::Beginner => Mistakes {
+                oversight: 0.5,
+                horizon: 1,
+                temperature: 0.5,
+            },
+            Self::Casual => Mistakes {
+                oversight: 0.3,
+                horizon: 2,
+                temperature: 0.2,
+            },
+            Self::Club => Mistakes {
+                oversight: 0.15,
+                horizon: 3,
+                temperature: 0.05,
+            },
+            Self::Expert => Mistakes {
+                oversight: 0.05,
+                horizon: 5,
+                temperature: 0.01,
+            },
+            Self::Perfect => Mistakes::NONE,
+        }
+    }
+}
+
+impl std::str::FromStr for Level {
+    type Err = ();
+
+    fn from_str(string: &str) -> std::result::Result<Self, Self::Err> {
+        match string {
+            "beginner" => Ok(Self::Beginner),
+            "casual" => Ok(Self::Casual),
+            "club" => Ok(Self::Club),
+            "expert" => Ok(Self::Expert),
+            "perfect" => Ok(Self::Perfect),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Beginner => write!(fmt, "beginner"),
+            Self::Casual => write!(fmt, "casual"),
+            Self::Club => write!(fmt, "club"),
+            Self::Expert => write!(fmt, "expert"),
+            Self::Perfect => write!(fmt, "perfect"),
+        }
+    }
+}
+
+// The chances of the AI behaving like a person would:
+//   - `oversight` is the probability of only looking `horizon` moves ahead, missing longer threats
+//   - `temperature` is how willing it is to pick a move scoring worse than the best one, relative
+//     to the spread of the scores. Zero means it always picks the best move
+struct Mistakes {
+    oversight: f64,
+    horizon: u8,
+    temperature: f64,
+}
+
+impl Mistakes {
+    const NONE: Self = Self {
+        oversight: 0.0,
+        horizon: 0,
+        temperature: 0.0,
+    };
+}
+
+// Handle to a running search, allowing it to be stopped from elsewhere
+#[derive(Clone, Default)]
+pub struct Search(std::sync::Arc<SearchState>);
+
+#[derive(Default)]
+struct SearchState {
+    running: std::sync::atomic::AtomicBool,
+    stop: std::sync::atomic::AtomicBool,
+}
+
+impl Search {
+    // Returns whether there was a search running to be stopped
+    pub fn stop(&self) -> bool {
+        self.0
+            .stop
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        self.0.running.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub fn stopped(&self) -> bool {
+        self.0.stop.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub fn begin(&self) {
+        self.0
+            .stop
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+        self.0
+            .running
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn end(&self) {
+        self.0
+            .running
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+pub struct Ai {
+    depth: u8,
+    mistakes: Mistakes,
+    ponder: bool,
+    pondering: std::cell::RefCell<Option<Ponder>>,
+    search: Search,
+    analysis: std::cell::RefCell<Vec<String>>,
+    evaluation: std::cell::Cell<Option<i64>>,
+    scores: std::cell::RefCell<Vec<(super::game::Move, i64)>>,
+    budget: Option<std::time::Duration>,
+    threads: Option<usize>,
+    rng: std::cell::RefCell<rand::rngs::StdRng>,
+}
+
+struct AiPlay<T> {
+    action: super::game::Move,
+    value: T,
+}
+
+// Analysis of the likely replies of the opponent, computed while they are thinking
+struct Ponder {
+    // Of the analysis of each reply
+    depth: u8,
+    search: Search,
+    handle: std::thread::JoinHandle<std::collections::HashMap<u64, Vec<AiPlay<i64>>>>,
+}
+
+impl Ponder {
+    const ORDERING_DEPTH: u8 = 4;
+
+    fn start<Game: super::game::Game + 'static>(
+        game: Game,
+        token: super::game::Token,
+        depth: u8,
+        threads: Option<usize>,
+        mut rng: rand::rngs::StdRng,
+    ) -> Self {
+        let search = Search::default();
+        search.begin();
+        let handle = {
+            let search = search.clone();
+            std::thread::spawn(move || {
+                let moves = Ai::shuffle_moves(&game, !token, &mut rng);
+                let ordering = Ai::affordable(Self::ORDERING_DEPTH, moves.len());
+                let mut replies = Ai::score(&game, !token, ordering, &moves, threads, &search);
+                replies.sort_by_key(|reply| std::cmp::Reverse(reply.value));
+
+                let mut analyses = std::collections::HashMap::new();
+                for reply in replies {
+                    if let Ok(next) = game.play(!token, reply.action) {
+                        if super::game::Status::Ongoing == next.status() {
+                            let moves = Ai::shuffle_moves(&next, token, &mut rng);
+                            let depth = Ai::affordable(depth, moves.len());
+                            let plays = Ai::score(&next, token, depth, &moves, threads, &search);
+                            if search.stopped() {
+                                break;
+                            }
+                            analyses.insert(Ai::key(&next, token), plays);
+                        }
+                    }
+                }
+                analyses
+            })
+        };
+        Self {
+            depth,
+            search,
+            handle,
+        }
+    }
+
+    fn finish(self) -> std::collections::HashMap<u64, Vec<AiPlay<i64>>> {
+        self.search.stop();
+        let analyses = self.handle.join().unwrap_or_default();
+        self.search.end();
+        analyses
+    }
+}
+
+impl Ai {
+    // Deepest search worth asking for when the time budget is what limits the AI
+    pub const MAX_DEPTH: u8 = 20;
+
+    pub fn new(depth: u8) -> Self {
+        use rand::SeedableRng;
+        Self {
+            depth,
+            mistakes: Mistakes::NONE,
+            ponder: false,
+            pondering: std::cell::RefCell::new(None),
+            search: Search::default(),
+            analysis: std::cell::RefCell::new(Vec::new()),
+            evaluation: std::cell::Cell::new(None),
+            scores: std::cell::RefCell::new(Vec::new()),
+            budget: None,
+            threads: None,
+            rng: std::cell::RefCell::new(rand::rngs::StdRng::from_entropy()),
+        }
+    }
+
+    pub fn with_level(level: Level) -> Self {
+        let mut ai = Self::new(level.depth());
+        ai.mistakes = level.mistakes();
+        ai
+    }
+
+    // Keep thinking on the likely replies while the opponent is playing
+    #[must_use]
+    pub fn pondering(mut self, ponder: bool) -> Self {
+        self.ponder = ponder;
+        self
+    }
+
+    // Stop searching deeper once the time is up, playing the best move found so far
+    #[must_use]
+    pub fn budget(mut self, budget: Option<std::time::Duration>) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    // Search with at most this many threads, rather than one per move
+    #[must_use]
+    pub fn threads(mut self, threads: Option<usize>) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    // Make the same choices every time it is given the same moves
+    #[must_use]
+    pub fn seeded(mut self, seed: Option<u64>) -> Self {
+        use rand::SeedableRng;
+        if let Some(seed) = seed {
+            self.rng = std::cell::RefCell::new(rand::rngs::StdRng::seed_from_u64(seed));
+        }
+        self
+    }
+
+    // Handle to stop the search, making the AI play the best move found so far
+    pub fn search(&self) -> Search {
+        self.search.clone()
+    }
+
+    // Description of how the last move was chosen
+    pub fn analysis(&self) -> Vec<String> {
+        self.analysis.borrow().clone()
+    }
+
+    // Score of every move searched for the last one, drops first, in column order
+    pub fn scores(&self) -> Vec<(super::game::Move, i64)> {
+        self.scores.borrow().clone()
+    }
+
+    // Score of the last move played, from the point of view of the AI
+    pub fn evaluation(&self) -> Option<i64> {
+        self.evaluation.get()
+    }
+
+    fn shuffle_moves<Game: super::game::Game>(
+        game: &Game,
+        token: super::game::Token,
+        rng: &mut rand::rngs::StdRng,
+    ) -> Vec<super::game::Move> {
+        use rand::seq::SliceRandom;
+        let mut moves = game.moves(token).collect::<Vec<_>>();
+        moves.shuffle(rng);
+        moves
+    }
+
+    fn key<Game: super::game::Game>(game: &Game, token: super::game::Token) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        game.hash(&mut hasher);
+        token.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn calculate_score<Game: super::game::Game>(
+        game: &Game,
+        action: super::game::Move,
+        token: super::game::Token,
+        depth: u8,
+        base: i64,
+        search: &Search,
+    ) -> Option<AiPlay<i64>> {
+        match game.play(token, action) {
+            Ok(game) => match game.status() {
+                super::game::Status::Victory => Some(AiPlay {
+                    action,
+                    value: base.saturating_pow(u32::from(depth)),
+                }),
+                super::game::Status::Defeat => Some(AiPlay {
+                    action,
+                    value: -base.saturating_pow(u32::from(depth)),
+                }),
+                _ if depth > 0 => Some(AiPlay {
+                    action,
+                    value: Self::dig(&game, depth - 1, !token, -1, base, search),
+                }),
+                _ => None,
+            },
+            Err(_) => None,
+        }
+    }
+
+    // Scores the moves in the given order, spread over the threads. Without a limit,
+    // each move gets its own thread
+    #[cfg(not(target_arch = "wasm32"))]
+    fn score<Game: super::game::Game>(
+        game: &Game,
+        token: super::game::Token,
+        depth: u8,
+        moves: &[super::game::Move],
+        threads: Option<usize>,
+        search: &Search,
+    ) -> Vec<AiPlay<i64>> {
+        let base = Self::base(moves.len());
+        let next = std::sync::atomic::AtomicUsize::new(0);
+        let workers = threads.unwrap_or(moves.len()).clamp(1, moves.len().max(1));
+
+        let mut plays = std::thread::scope(|scope| {
+            (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut plays = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                            let Some(&action) = moves.get(index) else {
+                                break plays;
+                            };
+                            if let Some(play) =
+                                Self::calculate_score(game, action, token, depth, base, search)
+                            {
+                                plays.push((index, play));
+                            }
+                        }
+                    })
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+                .filter_map(|worker| worker.join().ok())
+                .flatten()
+                .collect::<Vec<_>>()
+        });
+
+        // Back in the order of the moves, so that ties are broken the same way however
+        // the work was spread
+        plays.sort_unstable_by_key(|(index, _)| *index);
+        plays.into_iter().map(|(_, play)| play).collect()
+    }
+
+    // Web pages cannot spawn threads, so the moves are scored one after the other
+    #[cfg(target_arch = "wasm32")]
+    fn score<Game: super::game::Game>(
+        game: &Game,
+        token: super::game::Token,
+        depth: u8,
+        moves: &[super::game::Move],
+        _threads: Option<usize>,
+        search: &Search,
+    ) -> Vec<AiPlay<i64>> {
+        let base = Self::base(moves.len());
+        moves
+            .iter()
+            .filter_map(|&action| Self::calculate_score(game, action, token, depth, base, search))
+            .collect()
+    }
+
+    pub fn best_move<Game: super::game::Game + 'static>(
+        &self,
+        game: &Game,
+        token: super::game::Token,
+    ) -> super::game::Move {
+        self.best_move_within(game, token, None)
+    }
+
+    // Keeping within the budget of the AI, and the limit if there is one
+    pub fn best_move_within<Game: super::game::Game + 'static>(
+        &self,
+        game: &Game,
+        token: super::game::Token,
+        limit: Option<std::time::Duration>,
+    ) -> super::game::Move {
+        let moves = game.moves(token).count();
+        let depth = Self::affordable(self.search_depth(), moves);
+        let pondered = self
+            .pondering
+            .borrow_mut()
+            .take()
+            .and_then(|ponder| {
+                let depth = ponder.depth;
+                ponder
+                    .finish()
+                    .remove(&Self::key(game, token))
+                    .map(|plays| (plays, depth))
+            })
+            .filter(|_| depth == Self::affordable(self.depth, moves));
+
+        let budget = match (self.budget, limit) {
+            (Some(budget), Some(limit)) => Some(budget.min(limit)),
+            (budget, limit) => budget.or(limit),
+        };
+        let mut analysis = Vec::new();
+        let (plays, reached) = if let Some(pondered) = pondered {
+            analysis.push(String::from("Reused pondered analysis"));
+            pondered
+        } else {
+            let (plays, reached) = self.deepen(game, token, depth, budget);
+            if reached < depth {
+                analysis.push(format!("Search stopped at depth {reached}"));
+            } else {
+                analysis.push(format!("Searched to depth {reached}"));
+            }
+            (plays, reached)
+        };
+
+        let mut scores = plays
+            .iter()
+            .map(|play| (play.action, play.value))
+            .collect::<Vec<_>>();
+        scores.sort_unstable();
+        analysis.extend(
+            scores
+                .iter()
+                .map(|(action, value)| format!("Score for {action}: {value}")),
+        );
+        self.analysis.replace(analysis);
+
+        let action = self.choose(plays).unwrap_or_else(|| {
+            use rand::seq::IteratorRandom;
+            use rand::Rng;
+            let mut rng = self.rng.borrow_mut();
+            game.moves(token)
+                .choose(&mut *rng)
+                .unwrap_or_else(|| super::game::Move::Drop(rng.gen_range(0, game.size())))
+        });
+        self.evaluation.set(
+            scores
+                .iter()
+                .find(|(scored, _)| *scored == action)
+                .map(|(_, value)| *value),
+        );
+        self.scores.replace(scores);
+
+        if self.ponder && cfg!(not(target_arch = "wasm32")) {
+            if let Ok(next) = game.play(token, action) {
+                if super::game::Status::Ongoing == next.status() {
+                    use rand::SeedableRng;
+                    let rng = rand::rngs::StdRng::from_rng(&mut *self.rng.borrow_mut());
+                    if let Ok(rng) = rng {
+                        // With a budget, only as deep as the search got, for the replies to be
+                        // analysed before the opponent plays
+                        let depth = if budget.is_some() {
+                            reached
+                        } else {
+                            self.depth
+                        };
+                        self.pondering.replace(Some(Ponder::start(
+                            next,
+                            token,
+                            depth,
+                            self.threads,
+                            rng,
+                        )));
+                    }
+                }
+            }
+        }
+
+        action
+    }
+
+    // Searches one level deeper at a time, so that stopping the search still leaves the
+    // analysis of the last completed depth, which is returned along with it
+    fn deepen<Game: super::game::Game>(
+        &self,
+        game: &Game,
+        token: super::game::Token,
+        depth: u8,
+        budget: Option<std::time::Duration>,
+    ) -> (Vec<AiPlay<i64>>, u8) {
+        self.search.begin();
+
+        // The timer gives up on stopping the search as soon as the search is over. Web pages
+        // have no thread to run it on, and only go by the depth
+        let (over, timer) = std::sync::mpsc::channel::<()>();
+        let budget = budget.filter(|_| cfg!(not(target_arch = "wasm32")));
+        let timer = budget.map(|budget| {
+            let search = self.search.clone();
+            std::thread::spawn(move || {
+                if let Err(std::sync::mpsc::RecvTimeoutError::Timeout) = timer.recv_timeout(budget)
+                {
+                    search.stop();
+                }
+            })
+        });
+
+        let moves = Self::shuffle_moves(game, token, &mut self.rng.borrow_mut());
+        let mut plays = Vec::new();
+        let mut reached = 0;
+        for current in 1..=depth.max(1) {
+            let analysis = Self::score(game, token, current, &moves, self.threads, &self.search);
+            // A single level is never cut short, as there is nothing to dig into
+            if current > 1 && self.search.stopped() {
+                break;
+            }
+            plays = analysis;
+            reached = current;
+        }
+
+        drop(over);
+        if let Some(timer) = timer {
+            let _ = timer.join();
+        }
+        self.search.end();
+        (plays, reached)
+    }
+
+    // A win is worth more than everything that can follow any other move, the sooner the more,
+    // which takes a power of the number of moves, as with the seven columns of the usual board
+    fn base(moves: usize) -> i64 {
+        use std::convert::TryFrom;
+        i64::try_from(moves).unwrap_or(i64::MAX).max(7)
+    }
+
+    // Each level multiplies the positions by the number of moves, so games with more of them,
+    // like those without gravity, are searched no deeper than seven columns would allow
+    fn affordable(depth: u8, moves: usize) -> u8 {
+        use std::convert::TryFrom;
+        let positions = 7_u128.saturating_pow(u32::from(depth));
+        let moves = u128::try_from(moves).unwrap_or(u128::MAX);
+        (1..=depth)
+            .take_while(|level| moves.saturating_pow(u32::from(*level)) <= positions)
+            .last()
+            .unwrap_or(1)
+    }
+
+    fn search_depth(&self) -> u8 {
+        use rand::Rng;
+        if self.mistakes.oversight > 0.0
+            && self.rng.borrow_mut().gen::<f64>() < self.mistakes.oversight
+        {
+            self.depth.min(self.mistakes.horizon)
+        } else {
+            self.depth
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn choose(&self, plays: Vec<AiPlay<i64>>) -> Option<super::game::Move> {
+        let best = plays.iter().map(|play| play.value).max()?;
+        let worst = plays.iter().map(|play| play.value).min()?;
+
+        if self.mistakes.temperature > 0.0 && best > worst {
+            use rand::distributions::Distribution;
+            let spread = (best - worst) as f64;
+            let weights = plays.iter().map(|play| {
+                (-((best - play.value) as f64 / spread) / self.mistakes.temperature).exp()
+            });
+            if let Ok(distribution) = rand::distributions::WeightedIndex::new(weights) {
+                return Some(plays[distribution.sample(&mut *self.rng.borrow_mut())].action);
+            }
+        }
+
+        plays
+            .into_iter()
+            .reduce(Self::max_score)
+            .map(|play| play.action)
+    }
+
+    fn dig<Game: super::game::Game>(
+        game: &Game,
+        depth: u8,
+        token: super::game::Token,
+        factor: i64,
+        base: i64,
+        search: &Search,
+    ) -> i64 {
+        if search.stopped() {
+            0
+        } else if depth > 0 {
+            game.moves(token)
+                .filter_map(|action| game.play(token, action).ok())
+                .map(|game| match game.status() {
+                    super::game::Status::Victory => factor * base.saturating_pow(u32::from(depth)),
+                    super::game::Status::Defeat => -factor * base.saturating_pow(u32::from(depth)),
+                    _ => Self::dig(&game, depth - 1, !token, -factor, base, search),
+                })
+                .fold(0, i64::saturating_add)
+        } else {
+            // Only wins on the way down count, the position at the horizon is worth nothing
+            0
+        }
+    }
+
+    fn max_score(left: AiPlay<i64>, right: AiPlay<i64>) -> AiPlay<i64> {
+        if left.value > right.value {
+            left
+        } else {
+            right
+        }
+    }
+}
+
+impl Drop for Ai {
+    fn drop(&mut self) {
+        if let Some(ponder) = self.pondering.get_mut().take() {
+            ponder.finish();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod ai {
+        use super::super::*;
+
+        #[test]
+        fn level_from_str() {
+            assert_eq!("beginner".parse::<Level>(), Ok(Level::Beginner));
+            assert_eq!("perfect".parse::<Level>(), Ok(Level::Perfect));
+            assert_eq!("Club".parse::<Level>(), Err(()));
+            assert_eq!("8".parse::<Level>(), Err(()));
+        }
+
+        #[test]
+        fn choose_best_without_mistakes() {
+            use super::super::super::game::Move;
+            let ai = Ai::new(4);
+            let plays = vec![
+                AiPlay {
+                    action: Move::Drop(0),
+                    value: 3,
+                },
+                AiPlay {
+                    action: Move::Pop(4),
+                    value: 49,
+                },
+                AiPlay {
+                    action: Move::Drop(2),
+                    value: -7,
+                },
+            ];
+            assert_eq!(ai.choose(plays), Some(Move::Pop(4)));
+            assert_eq!(ai.choose(vec![]), None);
+        }
+
+        #[test]
+        fn choose_never_picks_unlikely_blunders() {
+            use super::super::super::game::Move;
+            let ai = Ai::with_level(Level::Expert);
+            for _ in 0..100 {
+                let plays = vec![
+                    AiPlay {
+                        action: Move::Drop(1),
+                        value: 343,
+                    },
+                    AiPlay {
+                        action: Move::Drop(5),
+                        value: -343,
+                    },
+                ];
+                assert_eq!(ai.choose(plays), Some(Move::Drop(1)));
+            }
+        }
+
+        #[test]
+        fn search_depth_within_horizon() {
+            let ai = Ai::with_level(Level::Beginner);
+            for _ in 0..100 {
+                let depth = ai.search_depth();
+                assert!(depth == 1 || depth == 2);
+            }
+            assert_eq!(Ai::with_level(Level::Perfect).search_depth(), 9);
+        }
+
+        #[test]
+        fn seeded_plays_alike() {
+            use super::super::super::game::{self, Token};
+            let game = game::new(7, 6, 4);
+            let moves = |threads| {
+                let ai = Ai::with_level(Level::Casual)
+                    .threads(threads)
+                    .seeded(Some(7));
+                (0..5)
+                    .map(|_| ai.best_move(&game, Token::White))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(moves(None), moves(Some(1)));
+            assert_eq!(moves(Some(3)), moves(Some(1)));
+        }
+
+        #[test]
+        fn pops_to_win() {
+            use super::super::super::game::{self, Move};
+            let moves = [0, 0, 0, 1, 1, 2, 2, 2].iter().copied().map(Move::Drop);
+            let (game, token) =
+                game::replay(game::PopOut::new(4, 3, 3), &moves.collect::<Vec<_>>()).unwrap();
+            assert_eq!(Ai::new(2).best_move(&game, token), Move::Pop(0));
+        }
+
+        #[test]
+        fn places_to_win() {
+            use super::super::super::game::{self, Move};
+            let moves = [(0, 0), (4, 4), (1, 1), (4, 3), (2, 2), (3, 4)]
+                .iter()
+                .map(|(x, y)| Move::Place(*x, *y))
+                .collect::<Vec<_>>();
+            let (game, token) = game::replay(game::Free::new(5, 5, 4), &moves).unwrap();
+            assert_eq!(Ai::new(8).best_move(&game, token), Move::Place(3, 3));
+            assert_eq!(Ai::affordable(8, 19), 5);
+            assert_eq!(Ai::affordable(8, 7), 8);
+        }
+
+        #[test]
+        fn wins_round_the_cylinder() {
+            use super::super::super::game::{self, Move, Variant};
+            let moves = [5, 2, 0, 2, 1, 2].iter().copied().map(Move::Drop);
+            let game = game::variant(Variant::Cylinder, 6, 4, 4);
+            let (game, token) = game::replay(game, &moves.collect::<Vec<_>>()).unwrap();
+            assert_eq!(Ai::new(2).best_move(&game, token), Move::Drop(4));
+        }
+
+        #[test]
+        fn budget_stops_the_search() {
+            use super::super::super::game::{self, Token};
+            let ai = Ai::new(Ai::MAX_DEPTH);
+            let budget = Some(std::time::Duration::from_millis(50));
+            let start = std::time::Instant::now();
+            let (plays, reached) =
+                ai.deepen(&game::new(7, 6, 4), Token::White, Ai::MAX_DEPTH, budget);
+            assert!(start.elapsed() < std::time::Duration::from_secs(5));
+            assert_eq!(plays.len(), 7);
+            assert!((1..Ai::MAX_DEPTH).contains(&reached));
+        }
+
+        #[test]
+        fn reuses_pondered_analysis() {
+            use super::super::super::game::{self, Game, Move, Token};
+            let ai = Ai::new(4).pondering(true).seeded(Some(3));
+            let game = game::new(7, 6, 4);
+            let game = game.play(Token::White, ai.best_move(&game, Token::White));
+            let game = game.unwrap().play(Token::Black, Move::Drop(0)).unwrap();
+
+            // Every reply is analysed once the pondering is done
+            while !ai.pondering.borrow().as_ref().unwrap().handle.is_finished() {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            ai.best_move(&game, Token::White);
+            assert_eq!(ai.analysis()[0], "Reused pondered analysis");
+        }
+
+        #[test]
+        fn ponders_within_budget() {
+            use super::super::super::game::{self, Game, Move, Token};
+            let ai = Ai::new(Ai::MAX_DEPTH)
+                .budget(Some(std::time::Duration::from_millis(100)))
+                .pondering(true)
+                .seeded(Some(3));
+            let game = game::new(7, 6, 4);
+            let game = game.play(Token::White, ai.best_move(&game, Token::White));
+            let game = game.unwrap().play(Token::Black, Move::Drop(0)).unwrap();
+
+            let start = std::time::Instant::now();
+            while !ai.pondering.borrow().as_ref().unwrap().handle.is_finished() {
+                assert!(start.elapsed() < std::time::Duration::from_secs(30));
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            ai.best_move(&game, Token::White);
+            assert_eq!(ai.analysis()[0], "Reused pondered analysis");
+        }
+
+        #[test]
+        fn dropped_while_pondering() {
+            use super::super::super::game::{self, Token};
+            let ai = Ai::new(Ai::MAX_DEPTH)
+                .budget(Some(std::time::Duration::from_millis(50)))
+                .pondering(true);
+            ai.best_move(&game::new(7, 6, 4), Token::White);
+            assert!(ai.pondering.borrow().is_some());
+
+            let start = std::time::Instant::now();
+            drop(ai);
+            assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        }
+    }
+}
@@ -0,0 +1,566 @@
+use clap::{Args, CommandFactory, Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(
+    name = "connect4",
+    version,
+    about = "Connect 4 for people and AIs",
+    after_help = "Defaults are read from $XDG_CONFIG_HOME/connect4/config.toml, or \
+                  ~/.config/connect4/config.toml, and the options take precedence",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    // Playing is what happens without a command
+    #[command(flatten)]
+    pub play: Play,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "Play a game, which is what happens without a command")]
+    Play(Play),
+    #[command(about = "Print the best move for a position")]
+    Solve(Position),
+    #[command(about = "Print the board and the score of every column for a position")]
+    Analyze(Position),
+    #[command(about = "Step through a saved game")]
+    Replay(Replay),
+    #[command(about = "Answer engine commands on the standard input, see `engine --help`")]
+    Engine(Engine),
+    #[command(about = "Answer engine commands over TCP, one game per connection")]
+    Serve(Serve),
+    #[command(about = "Answer JSON requests over HTTP, see `http --help`")]
+    Http(Http),
+    #[command(about = "Host games over WebSocket, see `host --help`")]
+    Host(Host),
+    #[command(about = "Play AIs against each other, each taking both colours, and rank them")]
+    Tournament(Tournament),
+    #[command(about = "Show the settings in effect, written like the configuration file")]
+    Config(Play),
+    #[command(about = "Print the completion script for a shell")]
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+}
+
+#[derive(Args)]
+pub struct Play {
+    #[arg(
+        value_name = "PLAYER",
+        num_args = 0..=2,
+        value_parser = spec,
+        help = "White then black: h for a human, a for the AI, a<depth> or a<level> \
+                where level is beginner, casual, club, expert or perfect, r for a player \
+                elsewhere, see --remote. A single player faces a human"
+    )]
+    pub players: Vec<super::config::Spec>,
+
+    #[arg(
+        long,
+        value_name = "PLAYER",
+        value_parser = spec,
+        help = "The white player, who moves first"
+    )]
+    pub white: Option<super::config::Spec>,
+
+    #[arg(long, value_name = "PLAYER", value_parser = spec, help = "The black player")]
+    pub black: Option<super::config::Spec>,
+
+    #[arg(short, long, help = "Print the analysis of the AI")]
+    pub verbose: bool,
+
+    #[arg(short, long, help = "Let the AI think during the turn of its opponent")]
+    pub ponder: bool,
+
+    #[arg(
+        short,
+        long,
+        value_name = "MS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "40",
+        help = "Animate falling tokens, taking MS milliseconds per row or 40 if MS is left \
+                out, 0 to disable"
+    )]
+    pub animate: Option<u64>,
+
+    #[arg(
+        short,
+        long,
+        value_parser = theme,
+        help = "Draw the tokens with a theme: colour, shade, ascii or contrast"
+    )]
+    pub theme: Option<super::ui::Theme>,
+
+    #[arg(
+        long,
+        value_name = "CONTROL",
+        value_parser = control,
+        help = "Time each player has for the game, plus an increment per move, like 5m+3s"
+    )]
+    pub time: Option<super::clock::Control>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Play a series of N games, the players swapping colours after each one, \
+                until one of them has won more than half [default: 1]"
+    )]
+    pub games: Option<u32>,
+
+    #[arg(
+        long,
+        help = "Play drawn games of a series again instead of counting them"
+    )]
+    pub replay_draws: bool,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Save every game to DIR, or nowhere if empty \
+                [default: ~/.local/share/connect4/games]"
+    )]
+    pub records: Option<std::path::PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Where the r player is, like ws://host:4001 for a new game hosted there, or \
+                ws://host:4001/GAME to join one"
+    )]
+    pub remote: Option<String>,
+
+    #[command(flatten)]
+    pub options: Options,
+}
+
+// Settings of the board and of the AI, shared by all commands
+#[derive(Args)]
+pub struct Options {
+    #[arg(long, help = "Depth of the AI players given without a level")]
+    pub depth: Option<u8>,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = duration,
+        help = "Time an AI may think per move, like 500ms, 2s or 1m"
+    )]
+    pub budget: Option<std::time::Duration>,
+
+    #[arg(long, value_parser = size, help = "Number of columns")]
+    pub width: Option<u8>,
+
+    #[arg(long, value_parser = size, help = "Number of rows")]
+    pub height: Option<u8>,
+
+    #[arg(long, value_parser = size, help = "Number of tokens in a line needed to win")]
+    pub win: Option<u8>,
+
+    #[arg(
+        long,
+        value_parser = variant,
+        help = "Rules to play by: classic, popout where players may also take one of their \
+                tokens out of the bottom row, pop10 where they fill the board then pop \
+                tokens of their lines to collect 10 of them, cylinder where lines go round \
+                from the last column to the first, or free where tokens go on any empty cell \
+                [default: classic]"
+    )]
+    pub variant: Option<super::game::Variant>,
+
+    #[arg(long, help = "Make the AI play the same way every time")]
+    pub seed: Option<u64>,
+
+    #[arg(
+        long,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "Threads of each AI [default: one per column]"
+    )]
+    pub threads: Option<usize>,
+}
+
+#[derive(Args)]
+pub struct Position {
+    #[arg(
+        value_parser = moves,
+        help = "Moves played so far, as columns counted from 1, like 4453, \
+                or 4,4,10,3 when there are more than 9 columns, p4 to pop column 4 and 4:2 \
+                for the cell of column 4 and row 2 [default: none]"
+    )]
+    pub moves: Option<Moves>,
+
+    #[command(flatten)]
+    pub options: Options,
+}
+
+#[derive(Args)]
+pub struct Replay {
+    #[arg(help = "The saved game")]
+    pub file: std::path::PathBuf,
+
+    #[arg(short, long, help = "Show the analysis of the AI at each position")]
+    pub analyze: bool,
+
+    // The board is the one of the game, whatever the options say
+    #[command(flatten)]
+    pub options: Options,
+}
+
+#[derive(Args)]
+#[command(after_help = "Commands, one per line, each answered by a line:
+    position [MOVES]   Start from the given moves, answering ok
+    play MOVE          Play a column counted from 1, p and a column to pop it, or a column and
+                       a row like 4:2, answering ok
+    go                 Answer info lines of analysis, then bestmove MOVE
+    board              Answer the board, then ok
+    quit               Stop
+Errors are answered by error and a message")]
+pub struct Engine {
+    #[arg(
+        long,
+        value_name = "PLAYER",
+        default_value = "a",
+        value_parser = spec,
+        help = "The AI, as a player given to play"
+    )]
+    pub ai: super::config::Spec,
+
+    #[command(flatten)]
+    pub options: Options,
+}
+
+#[derive(Args)]
+pub struct Serve {
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        default_value = "127.0.0.1:4000",
+        help = "Address to listen on"
+    )]
+    pub listen: String,
+
+    #[command(flatten)]
+    pub engine: Engine,
+}
+
+#[derive(Args)]
+#[command(
+    after_help = "Endpoints, taking and answering JSON, with columns counted from 1:
+    POST /move              The move of the AI and the scores of the columns
+    POST /solve             The best move and the scores of the columns
+    POST /validate          The board and who is to play, or why the moves cannot be played
+    POST /games             Start a game against the AI
+    GET  /games/{id}        The game
+    POST /games/{id}/move   Play a column, answering the game after the reply of the AI
+Requests may give moves, column, colour (of the human in a game), width, height, win, ai and budget,
+like {\"moves\": [4, 4], \"ai\": \"aclub\", \"budget\": \"1s\"}"
+)]
+pub struct Http {
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        default_value = "127.0.0.1:8080",
+        help = "Address to listen on"
+    )]
+    pub listen: String,
+
+    #[arg(
+        long,
+        value_name = "PLAYER",
+        default_value = "a",
+        value_parser = spec,
+        help = "The AI of requests that do not give one, as a player given to play"
+    )]
+    pub ai: super::config::Spec,
+
+    #[command(flatten)]
+    pub options: Options,
+}
+
+#[derive(Args)]
+#[command(
+    after_help = "Messages are JSON objects with a type, and columns count from 1:
+    {\"type\": \"join\", \"game\": ID, \"colour\": \"black\"}   Take a seat, in a new game without an ID
+    {\"type\": \"rejoin\", \"game\": ID, \"token\": TOKEN}    Take it back after losing the connection
+    {\"type\": \"move\", \"column\": 4}                     Play on the seat taken
+The host answers joined, with the game, colour and token, or error, and pushes every move and the \
+state of the game to both seats. Terminals play with `connect4 h r --remote ws://ADDRESS[/ID]`"
+)]
+pub struct Host {
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        default_value = "127.0.0.1:4001",
+        help = "Address to listen on"
+    )]
+    pub listen: String,
+
+    #[command(flatten)]
+    pub options: Options,
+}
+
+#[derive(Args)]
+pub struct Tournament {
+    #[arg(
+        value_name = "PLAYER",
+        num_args = 2..,
+        required = true,
+        value_parser = spec,
+        help = "AI players, as given to play"
+    )]
+    pub players: Vec<super::config::Spec>,
+
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Times each AI plays each other with both colours"
+    )]
+    pub rounds: u32,
+
+    #[command(flatten)]
+    pub options: Options,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Moves(pub Vec<super::game::Move>);
+
+impl Play {
+    // Positional players are replaced by --white and --black
+    pub fn apply(&self, settings: &mut super::config::Settings) {
+        let mut players = self.players.iter().copied();
+        if let Some(white) = players.next() {
+            settings.white = white;
+            settings.black = players.next().unwrap_or(super::config::Spec::Human);
+        }
+        if let Some(white) = self.white {
+            settings.white = white;
+        }
+        if let Some(black) = self.black {
+            settings.black = black;
+        }
+
+        settings.verbose |= self.verbose;
+        settings.ponder |= self.ponder;
+        if let Some(delay) = self.animate {
+            settings.animation = Some(delay)
+                .filter(|delay| *delay > 0)
+                .map(std::time::Duration::from_millis);
+        }
+        if let Some(theme) = self.theme {
+            settings.theme = theme;
+        }
+        settings.time = self.time.or(settings.time);
+        settings.games = self.games.unwrap_or(settings.games);
+        settings.replay_draws |= self.replay_draws;
+        if let Some(records) = &self.records {
+            settings.records =
+                Some(records.clone()).filter(|records| !records.as_os_str().is_empty());
+        }
+        settings.remote = self.remote.clone().or(settings.remote.take());
+        self.options.apply(settings);
+    }
+}
+
+impl Options {
+    pub fn apply(&self, settings: &mut super::config::Settings) {
+        settings.depth = self.depth.or(settings.depth);
+        settings.budget = self.budget.or(settings.budget);
+        settings.width = self.width.unwrap_or(settings.width);
+        settings.height = self.height.unwrap_or(settings.height);
+        settings.win = self.win.unwrap_or(settings.win);
+        settings.variant = self.variant.unwrap_or(settings.variant);
+        settings.seed = self.seed.or(settings.seed);
+        settings.threads = self.threads.or(settings.threads);
+
+        if let Err(message) = settings.check() {
+            fail(message);
+        }
+    }
+}
+
+// Exits like on any other invalid argument
+pub fn fail(message: impl std::fmt::Display) -> ! {
+    Cli::command()
+        .error(clap::error::ErrorKind::ValueValidation, message)
+        .exit()
+}
+
+pub fn completions(shell: clap_complete::Shell) {
+    let mut command = Cli::command();
+    clap_complete::generate(shell, &mut command, "connect4", &mut std::io::stdout());
+}
+
+fn spec(string: &str) -> Result<super::config::Spec, String> {
+    string.parse().map_err(|()| {
+        String::from(
+            "expected h, a, a<depth>, a<level> where level is beginner, casual, club, \
+             expert or perfect, or r",
+        )
+    })
+}
+
+fn theme(string: &str) -> Result<super::ui::Theme, String> {
+    string
+        .parse()
+        .map_err(|()| String::from("expected colour, shade, ascii or contrast"))
+}
+
+fn variant(string: &str) -> Result<super::game::Variant, String> {
+    string
+        .parse()
+        .map_err(|()| String::from("expected classic, popout, pop10, cylinder or free"))
+}
+
+fn duration(string: &str) -> Result<std::time::Duration, String> {
+    super::config::parse_duration(string)
+        .filter(|duration| !duration.is_zero())
+        .ok_or_else(|| String::from("expected a duration like 500ms, 2s or 1m"))
+}
+
+fn control(string: &str) -> Result<super::clock::Control, String> {
+    string
+        .parse()
+        .map_err(|()| String::from("expected a time and an increment, like 5m+3s or 90s"))
+}
+
+fn size(string: &str) -> Result<u8, String> {
+    string
+        .parse::<u8>()
+        .ok()
+        .filter(|size| (1..=super::config::Settings::MAX_SIZE).contains(size))
+        .ok_or_else(|| {
+            format!(
+                "expected a number from 1 to {}",
+                super::config::Settings::MAX_SIZE
+            )
+        })
+}
+
+// Single digits, or numbers separated by commas or spaces, a p in front of a column popping it
+// and a colon joining a column to a row
+pub fn moves(string: &str) -> Result<Moves, String> {
+    let words = if string.contains(|c: char| c == ',' || c.is_whitespace()) {
+        string
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+    } else {
+        let mut words = Vec::new();
+        let mut start = 0;
+        for (index, c) in string.char_indices() {
+            let joined = c == 'p' || c == ':' || string[index + c.len_utf8()..].starts_with(':');
+            if !joined {
+                words.push(&string[start..index + c.len_utf8()]);
+                start = index + c.len_utf8();
+            }
+        }
+        if start < string.len() {
+            words.push(&string[start..]);
+        }
+        words
+    };
+
+    words
+        .into_iter()
+        .map(|word| word.parse().ok())
+        .collect::<Option<Vec<_>>>()
+        .map(Moves)
+        .ok_or_else(|| {
+            String::from(
+                "expected columns counted from 1, like 4453 or 4,4,10,3, p4 popping column 4 \
+                 and 4:2 the cell of column 4 and row 2",
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    mod cli {
+        use super::super::*;
+
+        #[test]
+        fn definition() {
+            Cli::command().debug_assert();
+        }
+
+        #[test]
+        fn players() {
+            use super::super::super::config::Spec;
+            let cli = Cli::try_parse_from(["connect4", "a6", "-v", "--black", "aclub"]).unwrap();
+            assert!(cli.command.is_none());
+            assert_eq!(cli.play.players, vec![Spec::Ai(Some(6))]);
+            assert!(cli.play.verbose);
+
+            let cli = Cli::try_parse_from(["connect4", "h", "r", "--remote", "ws://c4:4001/9a"]);
+            let play = cli.unwrap().play;
+            assert_eq!(play.players, vec![Spec::Human, Spec::Remote]);
+            assert_eq!(play.remote.as_deref(), Some("ws://c4:4001/9a"));
+
+            let cli = Cli::try_parse_from([
+                "connect4",
+                "play",
+                "-a",
+                "h",
+                "--width=9",
+                "--time=5m+3s",
+                "--games",
+                "5",
+            ])
+            .unwrap();
+            match cli.command {
+                Some(Command::Play(play)) => {
+                    assert_eq!(play.animate, Some(40));
+                    assert_eq!(play.players, vec![Spec::Human]);
+                    assert_eq!(play.options.width, Some(9));
+                    assert_eq!(play.games, Some(5));
+                    assert_eq!(
+                        play.time.map(|time| time.to_string()).as_deref(),
+                        Some("5m+3s")
+                    );
+                }
+                _ => panic!("Expected to play"),
+            }
+
+            assert!(Cli::try_parse_from(["connect4", "b"]).is_err());
+            assert!(Cli::try_parse_from(["connect4", "h", "h", "h"]).is_err());
+            assert!(Cli::try_parse_from(["connect4", "--width", "17"]).is_err());
+            assert!(Cli::try_parse_from(["connect4", "--time", "3s+"]).is_err());
+            assert!(Cli::try_parse_from(["connect4", "--games", "0"]).is_err());
+            assert!(Cli::try_parse_from(["connect4", "tournament", "a2"]).is_err());
+        }
+
+        #[test]
+        fn moves() {
+            use super::super::super::game::Move::{Drop, Place, Pop};
+            assert_eq!(
+                super::super::moves("4453"),
+                Ok(Moves(vec![Drop(3), Drop(3), Drop(4), Drop(2)]))
+            );
+            assert_eq!(
+                super::super::moves("4, 10,p1"),
+                Ok(Moves(vec![Drop(3), Drop(9), Pop(0)]))
+            );
+            assert_eq!(super::super::moves("4p4"), Ok(Moves(vec![Drop(3), Pop(3)])));
+            assert_eq!(
+                super::super::moves("4:21:1"),
+                Ok(Moves(vec![Place(3, 1), Place(0, 0)]))
+            );
+            assert_eq!(
+                super::super::moves("4:2 10:10"),
+                Ok(Moves(vec![Place(3, 1), Place(9, 9)]))
+            );
+            assert_eq!(super::super::moves(""), Ok(Moves(vec![])));
+            assert!(super::super::moves("40").is_err());
+            assert!(super::super::moves("4a").is_err());
+            assert!(super::super::moves("4p").is_err());
+            assert!(super::super::moves("pp4").is_err());
+            assert!(super::super::moves("4:").is_err());
+        }
+    }
+}
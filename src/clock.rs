@@ -0,0 +1,196 @@
+use std::convert::TryFrom;
+
+// Time each player starts with, and the increment added after each of their moves,
+// written like 5m+3s
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Control {
+    pub base: std::time::Duration,
+    pub increment: std::time::Duration,
+}
+
+impl std::str::FromStr for Control {
+    type Err = ();
+
+    fn from_str(string: &str) -> std::result::Result<Self, Self::Err> {
+        let (base, increment) = string.split_once('+').unwrap_or((string, "0s"));
+        let base = super::config::parse_duration(base)
+            .filter(|base| !base.is_zero())
+            .ok_or(())?;
+        let increment = super::config::parse_duration(increment).ok_or(())?;
+        Ok(Self { base, increment })
+    }
+}
+
+impl std::fmt::Display for Control {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_duration(fmt, self.base)?;
+        if !self.increment.is_zero() {
+            write!(fmt, "+")?;
+            write_duration(fmt, self.increment)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_duration(
+    fmt: &mut std::fmt::Formatter<'_>,
+    duration: std::time::Duration,
+) -> std::fmt::Result {
+    let millis = duration.as_millis();
+    if millis % 60_000 == 0 {
+        write!(fmt, "{}m", millis / 60_000)
+    } else if millis % 1000 == 0 {
+        write!(fmt, "{}s", millis / 1000)
+    } else {
+        write!(fmt, "{millis}ms")
+    }
+}
+
+// Time left for each player, counting down for the one whose turn it is
+#[derive(Clone)]
+pub struct Clock {
+    control: Control,
+    white: std::time::Duration,
+    black: std::time::Duration,
+    running: Option<(super::game::Token, std::time::Instant)>,
+}
+
+impl Clock {
+    // Share of the time the AI keeps for the moves after the current one
+    const RESERVE: u32 = 2;
+
+    pub fn new(control: Control) -> Self {
+        Self {
+            control,
+            white: control.base,
+            black: control.base,
+            running: None,
+        }
+    }
+
+    // Keeps counting if the turn of the player had already started
+    pub fn start(&mut self, token: super::game::Token) {
+        match self.running {
+            Some((running, _)) if running == token => {}
+            _ => self.running = Some((token, std::time::Instant::now())),
+        }
+    }
+
+    // Ends the turn after a move, adding the increment
+    pub fn stop(&mut self) {
+        if let Some((token, _)) = self.running {
+            let remaining = self.remaining(token) + self.control.increment;
+            match token {
+                super::game::Token::White => self.white = remaining,
+                super::game::Token::Black => self.black = remaining,
+            }
+            self.running = None;
+        }
+    }
+
+    pub fn remaining(&self, token: super::game::Token) -> std::time::Duration {
+        let left = match token {
+            super::game::Token::White => self.white,
+            super::game::Token::Black => self.black,
+        };
+        match self.running {
+            Some((running, since)) if running == token => left.saturating_sub(since.elapsed()),
+            _ => left,
+        }
+    }
+
+    // Whether the player whose turn it is ran out of time
+    pub fn expired(&self) -> bool {
+        self.running
+            .is_some_and(|(token, _)| self.remaining(token).is_zero())
+    }
+
+    // Time for the next move, spreading what is left over the moves the player might still
+    // have to make, which is half the empty cells
+    pub fn budget<Game: super::game::Game>(
+        &self,
+        token: super::game::Token,
+        game: &Game,
+    ) -> std::time::Duration {
+        let cells = usize::from(game.size()) * usize::from(game.height());
+        let empty = cells - game.move_count();
+        let moves = u32::try_from(empty / 2).unwrap_or(u32::MAX).max(1);
+
+        let remaining = self.remaining(token);
+        (remaining / moves + self.control.increment).min(remaining / Self::RESERVE)
+    }
+
+    // Like 4:05, or 0:09.5 under ten seconds
+    pub fn format(&self, token: super::game::Token) -> String {
+        let remaining = self.remaining(token);
+        let seconds = remaining.as_secs();
+        if seconds < 10 {
+            format!("0:0{}.{}", seconds, remaining.subsec_millis() / 100)
+        } else {
+            format!("{}:{:02}", seconds / 60, seconds % 60)
+        }
+    }
+
+    pub fn running(&self) -> Option<super::game::Token> {
+        self.running.map(|(token, _)| token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod clock {
+        use super::super::*;
+        use crate::game::Token;
+        use std::time::Duration;
+
+        #[test]
+        fn control() {
+            let control = "5m+3s".parse::<Control>().unwrap();
+            assert_eq!(control.base, Duration::from_secs(5 * 60));
+            assert_eq!(control.increment, Duration::from_secs(3));
+            assert_eq!(control.to_string(), "5m+3s");
+            assert_eq!("90s".parse::<Control>().unwrap().to_string(), "90s");
+            assert_eq!(
+                "1.5s+250ms".parse::<Control>().unwrap().to_string(),
+                "1500ms+250ms"
+            );
+            assert!("0s+3s".parse::<Control>().is_err());
+            assert!("5m+".parse::<Control>().is_err());
+            assert!("fast".parse::<Control>().is_err());
+        }
+
+        #[test]
+        fn turns() {
+            let mut clock = Clock::new("1m+2s".parse().unwrap());
+            clock.start(Token::White);
+            assert_eq!(clock.running(), Some(Token::White));
+            assert!(!clock.expired());
+            clock.stop();
+
+            assert_eq!(clock.running(), None);
+            assert!(clock.remaining(Token::White) > Duration::from_secs(61));
+            assert_eq!(clock.remaining(Token::Black), Duration::from_secs(60));
+            assert_eq!(clock.format(Token::Black), "1:00");
+        }
+
+        #[test]
+        fn expiry() {
+            let mut clock = Clock::new("10ms".parse().unwrap());
+            clock.start(Token::Black);
+            std::thread::sleep(Duration::from_millis(20));
+            assert!(clock.expired());
+            assert_eq!(clock.format(Token::Black), "0:00.0");
+            assert_eq!(clock.format(Token::White), "0:00.0");
+        }
+
+        #[test]
+        fn budget() {
+            let clock = Clock::new("84s".parse().unwrap());
+            let game = crate::game::new(7, 6, 4);
+            assert_eq!(clock.budget(Token::White, &game), Duration::from_secs(4));
+
+            let clock = Clock::new("10s+5s".parse().unwrap());
+            assert_eq!(clock.budget(Token::White, &game), Duration::from_secs(5));
+        }
+    }
+}
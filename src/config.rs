@@ -0,0 +1,495 @@
+// Preferences read from $XDG_CONFIG_HOME/connect4/config.toml, or ~/.config/connect4/config.toml,
+// every one of them optional:
+//
+//   white = "a"          # Players, as given on the command line
+//   black = "h"
+//   depth = 8            # Depth of the AI players given without a level
+//   budget = "2s"        # Time an AI may think per move, in ms, s or m
+//   time = "5m+3s"       # Time each player has for the game, plus an increment per move
+//   games = 5            # Games in a series, the players swapping colours after each one
+//   replay_draws = false # Whether drawn games of a series are played again
+//   records = "/srv/c4"   # Directory each game is saved to, "" to not save them
+//   theme = "colour"
+//   width = 7
+//   height = 7
+//   win = 4              # Tokens in a line needed to win
+//   variant = "classic"  # Rules, classic, popout, pop10, cylinder or free
+//   seed = 42            # Makes the AI play the same way every time
+//   threads = 4          # Threads of each AI, one per column if unset
+//   verbose = false
+//   ponder = false
+//   animation = 40       # Milliseconds per row, off if unset or 0
+#[derive(Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    white: Option<String>,
+    black: Option<String>,
+    depth: Option<u8>,
+    budget: Option<String>,
+    time: Option<String>,
+    games: Option<u32>,
+    replay_draws: Option<bool>,
+    records: Option<String>,
+    theme: Option<String>,
+    width: Option<u8>,
+    height: Option<u8>,
+    win: Option<u8>,
+    variant: Option<String>,
+    seed: Option<u64>,
+    threads: Option<usize>,
+    verbose: Option<bool>,
+    ponder: Option<bool>,
+    animation: Option<u64>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Read(std::path::PathBuf, std::io::Error),
+    Parse(std::path::PathBuf, Box<toml::de::Error>),
+    Invalid(std::path::PathBuf, String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Read(path, e) => write!(fmt, "Could not read {}: {e}", path.display()),
+            Self::Parse(path, e) => {
+                write!(fmt, "Invalid configuration in {}: {e}", path.display())
+            }
+            Self::Invalid(path, message) => {
+                write!(
+                    fmt,
+                    "Invalid configuration in {}: {message}",
+                    path.display()
+                )
+            }
+        }
+    }
+}
+
+// A player as given on the command line or in the configuration
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Spec {
+    Human,
+    // Searching to the given depth, or the configured one
+    Ai(Option<u8>),
+    Level(super::ai::Level),
+    // Playing elsewhere, in the game hosted at the remote address
+    Remote,
+}
+
+impl std::str::FromStr for Spec {
+    type Err = ();
+
+    fn from_str(string: &str) -> std::result::Result<Self, Self::Err> {
+        if string == "h" {
+            Ok(Self::Human)
+        } else if string == "r" {
+            Ok(Self::Remote)
+        } else if let Some(level) = string.strip_prefix('a') {
+            if level.is_empty() {
+                Ok(Self::Ai(None))
+            } else if let Ok(depth) = level.parse::<u8>() {
+                Ok(Self::Ai(Some(depth)))
+            } else {
+                level.parse().map(Self::Level)
+            }
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl std::fmt::Display for Spec {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Human => write!(fmt, "h"),
+            Self::Ai(None) => write!(fmt, "a"),
+            Self::Ai(Some(depth)) => write!(fmt, "a{depth}"),
+            Self::Level(level) => write!(fmt, "a{level}"),
+            Self::Remote => write!(fmt, "r"),
+        }
+    }
+}
+
+// The configuration with the defaults filled in, for the command line to override
+#[derive(Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Settings {
+    pub path: Option<std::path::PathBuf>,
+    pub loaded: bool,
+    pub white: Spec,
+    pub black: Spec,
+    pub depth: Option<u8>,
+    pub budget: Option<std::time::Duration>,
+    pub time: Option<super::clock::Control>,
+    pub games: u32,
+    pub replay_draws: bool,
+    pub records: Option<std::path::PathBuf>,
+    // Only ever given on the command line, as it names a game
+    pub remote: Option<String>,
+    pub theme: super::ui::Theme,
+    pub width: u8,
+    pub height: u8,
+    pub win: u8,
+    pub variant: super::game::Variant,
+    pub seed: Option<u64>,
+    pub threads: Option<usize>,
+    pub verbose: bool,
+    pub ponder: bool,
+    pub animation: Option<std::time::Duration>,
+}
+
+impl Config {
+    pub fn path() -> Option<std::path::PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .map(std::path::PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".config"))
+            })?;
+        Some(base.join("connect4").join("config.toml"))
+    }
+
+    // A missing file is the same as an empty one
+    pub fn load() -> Result<Settings, Error> {
+        let Some(path) = Self::path() else {
+            return Ok(Settings::default());
+        };
+
+        let (config, loaded) = match std::fs::read_to_string(&path) {
+            Ok(text) => (
+                Self::parse(&text).map_err(|e| Error::Parse(path.clone(), e))?,
+                true,
+            ),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (Self::default(), false),
+            Err(e) => return Err(Error::Read(path, e)),
+        };
+
+        let mut settings = config
+            .settings()
+            .map_err(|message| Error::Invalid(path.clone(), message))?;
+        settings.path = Some(path);
+        settings.loaded = loaded;
+        Ok(settings)
+    }
+
+    fn parse(text: &str) -> Result<Self, Box<toml::de::Error>> {
+        toml::from_str(text).map_err(Box::new)
+    }
+
+    fn settings(&self) -> Result<Settings, String> {
+        let player = |name: &str, spec: &Option<String>, default: Spec| {
+            spec.as_deref().map_or(Ok(default), |spec| {
+                spec.parse()
+                    .map_err(|()| format!("{name} is not a player: {spec}"))
+            })
+        };
+
+        let budget = match &self.budget {
+            Some(budget) => Some(
+                parse_duration(budget)
+                    .filter(|budget| !budget.is_zero())
+                    .ok_or_else(|| format!("budget is not a duration: {budget}"))?,
+            ),
+            None => None,
+        };
+
+        let time = match &self.time {
+            Some(time) => Some(
+                time.parse()
+                    .map_err(|()| format!("time is not a time control: {time}"))?,
+            ),
+            None => None,
+        };
+
+        let theme = match &self.theme {
+            Some(theme) => theme
+                .parse()
+                .map_err(|()| format!("theme is not known: {theme}"))?,
+            None => super::ui::Theme::detect(),
+        };
+
+        let variant = match &self.variant {
+            Some(variant) => variant
+                .parse()
+                .map_err(|()| format!("variant is not known: {variant}"))?,
+            None => super::game::Variant::Classic,
+        };
+
+        let settings = Settings {
+            path: None,
+            loaded: false,
+            white: player("white", &self.white, Spec::Ai(None))?,
+            black: player("black", &self.black, Spec::Human)?,
+            depth: self.depth,
+            budget,
+            time,
+            games: self.games.unwrap_or(1),
+            replay_draws: self.replay_draws.unwrap_or(false),
+            records: match &self.records {
+                Some(records) if records.is_empty() => None,
+                Some(records) => Some(std::path::PathBuf::from(records)),
+                None => super::record::directory(),
+            },
+            remote: None,
+            theme,
+            width: self.width.unwrap_or(super::game::WIDTH),
+            height: self.height.unwrap_or(super::game::HEIGHT),
+            win: self.win.unwrap_or(super::game::WIN),
+            variant,
+            seed: self.seed,
+            threads: self.threads,
+            verbose: self.verbose.unwrap_or(false),
+            ponder: self.ponder.unwrap_or(false),
+            animation: self
+                .animation
+                .filter(|delay| *delay > 0)
+                .map(std::time::Duration::from_millis),
+        };
+        settings.check()?;
+        Ok(settings)
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Config::default()
+            .settings()
+            .expect("The defaults are valid")
+    }
+}
+
+impl Settings {
+    pub const MAX_SIZE: u8 = 16;
+
+    // Catches what each setting cannot tell on its own
+    pub fn check(&self) -> Result<(), String> {
+        for (name, size) in [("width", self.width), ("height", self.height)] {
+            if !(1..=Self::MAX_SIZE).contains(&size) {
+                return Err(format!("{name} must be from 1 to {}", Self::MAX_SIZE));
+            }
+        }
+
+        let longest = self.width.max(self.height);
+        if self.win < 2 || self.win > longest {
+            return Err(format!(
+                "win must be from 2 to {longest}, the longest side of the board"
+            ));
+        }
+
+        if self.games == 0 {
+            return Err(String::from("games must be at least 1"));
+        }
+
+        if self.threads == Some(0) {
+            return Err(String::from("threads must be at least 1"));
+        }
+        Ok(())
+    }
+
+    // Without a depth, a time budget or the clock is what limits the search
+    pub fn depth(&self) -> u8 {
+        self.depth
+            .unwrap_or(if self.budget.is_some() || self.time.is_some() {
+                super::ai::Ai::MAX_DEPTH
+            } else {
+                8
+            })
+    }
+
+    // Remote players join their game as soon as they are made, playing the given colour
+    pub fn player(
+        &self,
+        spec: Spec,
+        token: super::game::Token,
+    ) -> Result<super::player::Player, String> {
+        use super::player::Player;
+        match spec {
+            Spec::Human => Ok(Player::Human),
+            Spec::Remote => {
+                let url = self
+                    .remote
+                    .as_deref()
+                    .ok_or_else(|| String::from("a remote player needs --remote"))?;
+                super::online::Remote::join(url, token, self).map(Player::Tcp)
+            }
+            spec => Ok(Player::Ai(self.ai(spec).expect("The player is an AI"))),
+        }
+    }
+
+    pub fn ai(&self, spec: Spec) -> Option<super::ai::Ai> {
+        use super::ai::Ai;
+        let ai = match spec {
+            Spec::Human | Spec::Remote => return None,
+            Spec::Ai(depth) => Ai::new(depth.unwrap_or_else(|| self.depth())),
+            Spec::Level(level) => Ai::with_level(level),
+        };
+        Some(
+            ai.pondering(self.ponder)
+                .budget(self.budget)
+                .threads(self.threads)
+                .seeded(self.seed),
+        )
+    }
+}
+
+// Written the way the configuration file is, commenting out what is not set
+impl std::fmt::Display for Settings {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.path {
+            Some(path) if self.loaded => writeln!(fmt, "# Read from {}", path.display())?,
+            Some(path) => writeln!(fmt, "# Defaults, as {} does not exist", path.display())?,
+            None => writeln!(fmt, "# Defaults, as there is no configuration directory")?,
+        }
+        writeln!(fmt, "white = \"{}\"", self.white)?;
+        writeln!(fmt, "black = \"{}\"", self.black)?;
+        writeln!(fmt, "depth = {}", self.depth())?;
+        match self.budget {
+            Some(budget) => writeln!(fmt, "budget = \"{budget:?}\"")?,
+            None => writeln!(fmt, "# budget is not limited")?,
+        }
+        match self.time {
+            Some(time) => writeln!(fmt, "time = \"{time}\"")?,
+            None => writeln!(fmt, "# time is not limited")?,
+        }
+        writeln!(fmt, "games = {}", self.games)?;
+        writeln!(fmt, "replay_draws = {}", self.replay_draws)?;
+        match &self.records {
+            Some(records) => writeln!(fmt, "records = {:?}", records.display().to_string())?,
+            None => writeln!(fmt, "records = \"\"")?,
+        }
+        writeln!(fmt, "theme = \"{}\"", self.theme)?;
+        writeln!(fmt, "width = {}", self.width)?;
+        writeln!(fmt, "height = {}", self.height)?;
+        writeln!(fmt, "win = {}", self.win)?;
+        writeln!(fmt, "variant = \"{}\"", self.variant)?;
+        match self.seed {
+            Some(seed) => writeln!(fmt, "seed = {seed}")?,
+            None => writeln!(fmt, "# seed is random")?,
+        }
+        match self.threads {
+            Some(threads) => writeln!(fmt, "threads = {threads}")?,
+            None => writeln!(fmt, "# threads are one per column")?,
+        }
+        writeln!(fmt, "verbose = {}", self.verbose)?;
+        writeln!(fmt, "ponder = {}", self.ponder)?;
+        writeln!(
+            fmt,
+            "animation = {}",
+            self.animation.map_or(0, |delay| delay.as_millis())
+        )
+    }
+}
+
+// Durations like 500ms, 2s, 1.5s or 1m
+pub fn parse_duration(string: &str) -> Option<std::time::Duration> {
+    let (number, unit) = string.split_at(string.find(|c: char| c.is_ascii_alphabetic())?);
+    let number = number.trim().parse::<f64>().ok()?;
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "s" => number,
+        "m" => number * 60.0,
+        _ => return None,
+    };
+    std::time::Duration::try_from_secs_f64(seconds).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    mod config {
+        use super::super::*;
+
+        #[test]
+        fn settings() {
+            let config = Config::parse(
+                r#"
+                white = "h"
+                black = "aclub"
+                budget = "1.5s"
+                games = 5
+                records = ""
+                theme = "ascii"
+                width = 9
+                height = 6
+                win = 5
+                variant = "popout"
+                seed = 42
+                threads = 2
+                animation = 0
+                "#,
+            )
+            .unwrap();
+            let settings = config.settings().unwrap();
+
+            assert_eq!(settings.white, Spec::Human);
+            assert_eq!(
+                settings.black,
+                Spec::Level(super::super::super::ai::Level::Club)
+            );
+            assert_eq!(settings.depth(), super::super::super::ai::Ai::MAX_DEPTH);
+            assert_eq!(
+                settings.budget,
+                Some(std::time::Duration::from_millis(1500))
+            );
+            assert_eq!((settings.games, settings.replay_draws), (5, false));
+            assert_eq!(settings.records, None);
+            assert_eq!(settings.theme, super::super::super::ui::Theme::Ascii);
+            assert_eq!((settings.width, settings.height, settings.win), (9, 6, 5));
+            assert_eq!(settings.variant, super::super::super::game::Variant::PopOut);
+            assert_eq!(settings.seed, Some(42));
+            assert_eq!(settings.threads, Some(2));
+            assert_eq!(settings.animation, None);
+
+            let settings = Config::parse("animation = 25").unwrap().settings().unwrap();
+            assert_eq!(
+                settings.animation,
+                Some(std::time::Duration::from_millis(25))
+            );
+            assert_eq!(Settings::default().animation, None);
+        }
+
+        #[test]
+        fn invalid() {
+            assert!(Config::parse("colour = \"red\"").is_err());
+            assert!(Config::parse("width = 300").is_err());
+
+            for text in &[
+                "white = \"b\"",
+                "budget = \"soon\"",
+                "time = \"0s+2s\"",
+                "games = 0",
+                "theme = \"neon\"",
+                "width = 17",
+                "height = 0",
+                "win = 8",
+                "win = 1",
+                "variant = \"pop\"",
+                "threads = 0",
+            ] {
+                assert!(Config::parse(text).unwrap().settings().is_err(), "{}", text);
+            }
+        }
+
+        #[test]
+        fn spec() {
+            for spec in &["h", "a", "a6", "aexpert", "r"] {
+                assert_eq!(spec.parse::<Spec>().unwrap().to_string(), *spec);
+            }
+            assert_eq!("a".parse::<Spec>(), Ok(Spec::Ai(None)));
+            assert_eq!("x".parse::<Spec>(), Err(()));
+            assert_eq!("aclubs".parse::<Spec>(), Err(()));
+        }
+
+        #[test]
+        fn duration() {
+            use std::time::Duration;
+            assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+            assert_eq!(parse_duration("2s"), Some(Duration::from_secs(2)));
+            assert_eq!(parse_duration("1.5m"), Some(Duration::from_secs(90)));
+            assert_eq!(parse_duration("2"), None);
+            assert_eq!(parse_duration("2h"), None);
+            assert_eq!(parse_duration("-1s"), None);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+// Line protocol letting other programs play against the AI, see `connect4 engine --help`
+pub fn run(
+    input: impl std::io::BufRead,
+    mut output: impl std::io::Write,
+    settings: &super::config::Settings,
+    ai: &super::ai::Ai,
+) -> std::io::Result<()> {
+    let mut moves = Vec::new();
+    for line in input.lines() {
+        let line = line?;
+        let (command, arguments) = line
+            .trim()
+            .split_once(char::is_whitespace)
+            .unwrap_or((line.trim(), ""));
+
+        let lines = match command {
+            "" => continue,
+            "quit" => break,
+            _ => answer(command, arguments.trim(), &mut moves, settings, ai)
+                .unwrap_or_else(|message| vec![format!("error {message}")]),
+        };
+        for line in lines {
+            writeln!(output, "{line}")?;
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
+
+// Runs the engine for every connection, each with its own AI and game
+pub fn serve(
+    listen: &str,
+    settings: &super::config::Settings,
+    ai: super::config::Spec,
+) -> std::io::Result<()> {
+    let listener = std::net::TcpListener::bind(listen)?;
+    eprintln!("Listening on {}", listener.local_addr()?);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Could not accept a connection: {e}");
+                continue;
+            }
+        };
+
+        let settings = settings.clone();
+        std::thread::spawn(move || {
+            let Some(ai) = settings.ai(ai) else {
+                return;
+            };
+            let result = stream
+                .try_clone()
+                .and_then(|input| run(std::io::BufReader::new(input), stream, &settings, &ai));
+            if let Err(e) = result {
+                eprintln!("Connection lost: {e}");
+            }
+        });
+    }
+    Ok(())
+}
+
+pub fn solve(
+    settings: &super::config::Settings,
+    moves: &[super::game::Move],
+) -> Result<(), String> {
+    let (game, token) = ongoing(settings, moves)?;
+    let ai = analyst(settings);
+    println!("{}", ai.best_move(&game, token));
+    Ok(())
+}
+
+pub fn analyze(
+    settings: &super::config::Settings,
+    moves: &[super::game::Move],
+) -> Result<(), String> {
+    let (game, token) = ongoing(settings, moves)?;
+    print!("{}", settings.theme.board(&game));
+    println!("{} to play", settings.theme.label(token));
+
+    let ai = analyst(settings);
+    let action = ai.best_move(&game, token);
+    for line in ai.analysis() {
+        println!("{line}");
+    }
+    println!("Best move: {action}");
+    Ok(())
+}
+
+fn answer(
+    command: &str,
+    arguments: &str,
+    moves: &mut Vec<super::game::Move>,
+    settings: &super::config::Settings,
+    ai: &super::ai::Ai,
+) -> Result<Vec<String>, String> {
+    use super::game::Game;
+    match command {
+        "position" => {
+            let super::cli::Moves(played) = super::cli::moves(arguments)?;
+            position(settings, &played)?;
+            *moves = played;
+            Ok(vec![String::from("ok")])
+        }
+        "play" => {
+            let action = arguments
+                .parse()
+                .map_err(|()| "expected a column counted from 1, or p and a column")?;
+            moves.push(action);
+            if let Err(message) = position(settings, moves) {
+                moves.pop();
+                return Err(message);
+            }
+            Ok(vec![String::from("ok")])
+        }
+        "go" => {
+            let (game, token) = ongoing(settings, moves)?;
+            let action = ai.best_move(&game, token);
+            let mut lines = ai
+                .analysis()
+                .into_iter()
+                .map(|line| format!("info {line}"))
+                .collect::<Vec<_>>();
+            lines.push(format!("bestmove {action}"));
+            Ok(lines)
+        }
+        "board" => {
+            let (game, _) = position(settings, moves)?;
+            let mut lines = super::ui::Theme::Ascii
+                .board(&game)
+                .lines()
+                .map(String::from)
+                .collect::<Vec<_>>();
+            lines.push(match game.status() {
+                super::game::Status::Ongoing | super::game::Status::Timeout => String::from("ok"),
+                super::game::Status::Victory => String::from("ok victory"),
+                super::game::Status::Defeat => String::from("ok defeat"),
+                super::game::Status::Tie => String::from("ok tie"),
+            });
+            Ok(lines)
+        }
+        _ => Err(format!("unknown command {command}")),
+    }
+}
+
+// The game after the moves, with who is to play
+pub fn position(
+    settings: &super::config::Settings,
+    moves: &[super::game::Move],
+) -> Result<(impl super::game::Game, super::game::Token), String> {
+    let game = super::game::variant(
+        settings.variant,
+        settings.width,
+        settings.height,
+        settings.win,
+    );
+    super::game::replay(game, moves).map_err(|(index, e)| {
+        format!(
+            "move {} in column {}: {e}",
+            index + 1,
+            moves[index].column() + 1
+        )
+    })
+}
+
+pub fn ongoing(
+    settings: &super::config::Settings,
+    moves: &[super::game::Move],
+) -> Result<(impl super::game::Game, super::game::Token), String> {
+    use super::game::Game;
+    let (game, token) = position(settings, moves)?;
+    if game.status() == super::game::Status::Ongoing {
+        Ok((game, token))
+    } else {
+        Err(String::from("the game is over"))
+    }
+}
+
+// Analyses are asked for one at a time, so there is nothing to ponder on
+pub fn analyst(settings: &super::config::Settings) -> super::ai::Ai {
+    super::ai::Ai::new(settings.depth())
+        .budget(settings.budget)
+        .threads(settings.threads)
+        .seeded(settings.seed)
+}
+
+#[cfg(test)]
+mod tests {
+    mod engine {
+        use super::super::*;
+
+        fn settings() -> super::super::super::config::Settings {
+            super::super::super::config::Settings {
+                depth: Some(4),
+                width: 7,
+                height: 6,
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn session() {
+            let settings = settings();
+            let ai = analyst(&settings);
+            let input = "position 1212\nplay 1\nplay 9\ngo\nbogus\n\nquit\ngo\n";
+            let mut output = Vec::new();
+            run(input.as_bytes(), &mut output, &settings, &ai).unwrap();
+
+            let output = String::from_utf8(output).unwrap();
+            let lines = output.lines().collect::<Vec<_>>();
+            assert_eq!(lines[0], "ok");
+            assert_eq!(lines[1], "ok");
+            assert_eq!(lines[2], "error move 6 in column 9: out of bounds");
+            assert!(lines[3].starts_with("info "));
+            // Black has to block the column white is about to complete
+            assert_eq!(lines[lines.len() - 2], "bestmove 1");
+            assert_eq!(lines[lines.len() - 1], "error unknown command bogus");
+        }
+
+        #[test]
+        fn over() {
+            let settings = settings();
+            let moves = [0, 1, 0, 1, 0, 1, 0]
+                .iter()
+                .copied()
+                .map(crate::game::Move::Drop);
+            assert_eq!(
+                ongoing(&settings, &moves.collect::<Vec<_>>()).err(),
+                Some(String::from("the game is over"))
+            );
+        }
+    }
+}
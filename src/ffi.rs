@@ -0,0 +1,216 @@
+// The game and the AI for C and C++, built with `cargo build --release --lib --features ffi`, and
+// declared in include/connect4.h, which cbindgen writes. Columns and rows count from 0, rows
+// from the top, and the game moves on in place:
+//
+//   Connect4Game *game = connect4_new(7, 6, 4);
+//   connect4_place(game, connect4_best_move(game, 8, 0));
+//   if (connect4_status(game) == CONNECT4_STATUS_ONGOING) { ... }
+//   connect4_free(game);
+//
+// Every game given to these functions must come from connect4_new and not be freed yet, which
+// is the safety contract of each of them
+#![allow(clippy::missing_safety_doc)]
+
+use super::game::Game;
+
+pub struct Connect4Game {
+    game: super::game::Connect4,
+    token: super::game::Token,
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub enum Connect4Result {
+    Ok = 0,
+    OutOfBounds = 1,
+    ColumnFull = 2,
+    Over = 3,
+    NoGame = 4,
+    CannotPop = 5,
+    Forbidden = 6,
+    Occupied = 7,
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub enum Connect4Status {
+    Ongoing = 0,
+    WhiteWon = 1,
+    BlackWon = 2,
+    Tie = 3,
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub enum Connect4Token {
+    Empty = 0,
+    White = 1,
+    Black = 2,
+}
+
+// Null when no board of that size can have that many in a row
+#[no_mangle]
+pub extern "C" fn connect4_new(width: u8, height: u8, win: u8) -> *mut Connect4Game {
+    let sides = 1..=127;
+    let longest = width.max(height);
+    if !sides.contains(&width) || !sides.contains(&height) || win < 2 || win > longest {
+        return std::ptr::null_mut();
+    }
+
+    Box::into_raw(Box::new(Connect4Game {
+        game: super::game::new(width, height, win),
+        token: super::game::Token::White,
+    }))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn connect4_free(game: *mut Connect4Game) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+// Drops a token of the player to move, leaving the game as it was when it cannot
+#[no_mangle]
+pub unsafe extern "C" fn connect4_place(game: *mut Connect4Game, column: u8) -> Connect4Result {
+    let Some(game) = game.as_mut() else {
+        return Connect4Result::NoGame;
+    };
+    if game.game.status() != super::game::Status::Ongoing {
+        return Connect4Result::Over;
+    }
+    match game.game.place(game.token, column) {
+        Ok(next) => {
+            game.game = next;
+            if game.game.status() == super::game::Status::Ongoing {
+                game.token = !game.token;
+            }
+            Connect4Result::Ok
+        }
+        Err(super::game::Error::OutOfBounds) => Connect4Result::OutOfBounds,
+        Err(super::game::Error::ColumnFull) => Connect4Result::ColumnFull,
+        Err(super::game::Error::Over) => Connect4Result::Over,
+        Err(super::game::Error::CannotPop) => Connect4Result::CannotPop,
+        Err(super::game::Error::Forbidden) => Connect4Result::Forbidden,
+        Err(super::game::Error::Occupied) => Connect4Result::Occupied,
+    }
+}
+
+// A missing game is never over
+#[no_mangle]
+pub unsafe extern "C" fn connect4_status(game: *const Connect4Game) -> Connect4Status {
+    let Some(game) = game.as_ref() else {
+        return Connect4Status::Ongoing;
+    };
+    match (game.game.status(), game.token) {
+        (super::game::Status::Victory, super::game::Token::White)
+        | (super::game::Status::Defeat, super::game::Token::Black) => Connect4Status::WhiteWon,
+        (super::game::Status::Victory, super::game::Token::Black)
+        | (super::game::Status::Defeat, super::game::Token::White) => Connect4Status::BlackWon,
+        (super::game::Status::Tie, _) => Connect4Status::Tie,
+        (super::game::Status::Ongoing | super::game::Status::Timeout, _) => Connect4Status::Ongoing,
+    }
+}
+
+// The player to move, or who won
+#[no_mangle]
+pub unsafe extern "C" fn connect4_turn(game: *const Connect4Game) -> Connect4Token {
+    game.as_ref()
+        .map_or(Connect4Token::Empty, |game| token(Some(game.token)))
+}
+
+// Empty outside of the board
+#[no_mangle]
+pub unsafe extern "C" fn connect4_cell(game: *const Connect4Game, x: u8, y: u8) -> Connect4Token {
+    game.as_ref()
+        .map_or(Connect4Token::Empty, |game| token(game.game.cell(x, y)))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn connect4_width(game: *const Connect4Game) -> u8 {
+    game.as_ref().map_or(0, |game| game.game.size())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn connect4_height(game: *const Connect4Game) -> u8 {
+    game.as_ref().map_or(0, |game| game.game.height())
+}
+
+// Column the AI plays for the player to move, searching to the given depth, and for at most the
+// given milliseconds unless they are 0. Negative when the game is missing or over
+#[no_mangle]
+pub unsafe extern "C" fn connect4_best_move(
+    game: *const Connect4Game,
+    depth: u8,
+    milliseconds: u32,
+) -> i16 {
+    let Some(game) = game.as_ref() else {
+        return -1;
+    };
+    if game.game.status() != super::game::Status::Ongoing {
+        return -1;
+    }
+
+    let budget = Some(std::time::Duration::from_millis(u64::from(milliseconds)))
+        .filter(|budget| !budget.is_zero());
+    let ai = super::ai::Ai::new(depth.max(1)).budget(budget);
+    i16::from(ai.best_move(&game.game, game.token).column())
+}
+
+fn token(token: Option<super::game::Token>) -> Connect4Token {
+    match token {
+        Some(super::game::Token::White) => Connect4Token::White,
+        Some(super::game::Token::Black) => Connect4Token::Black,
+        None => Connect4Token::Empty,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod ffi {
+        use super::super::*;
+
+        #[test]
+        fn header() {
+            let generated = include_str!(concat!(env!("OUT_DIR"), "/connect4.h"));
+            assert!(
+                include_str!("../include/connect4.h") == generated,
+                "include/connect4.h is out of date, see build.rs to write it again"
+            );
+        }
+
+        #[test]
+        fn game() {
+            unsafe {
+                assert!(connect4_new(0, 6, 4).is_null());
+                assert!(connect4_new(7, 6, 8).is_null());
+
+                let game = connect4_new(4, 4, 3);
+                assert_eq!(connect4_width(game), 4);
+                assert_eq!(connect4_place(game, 4), Connect4Result::OutOfBounds);
+                for column in [0, 3, 1] {
+                    assert_eq!(connect4_place(game, column), Connect4Result::Ok);
+                }
+                assert_eq!(connect4_cell(game, 3, 3), Connect4Token::Black);
+                assert_eq!(connect4_cell(game, 3, 2), Connect4Token::Empty);
+                assert_eq!(connect4_cell(game, 9, 9), Connect4Token::Empty);
+                assert_eq!(connect4_turn(game), Connect4Token::Black);
+
+                // Black has to block the third in a row
+                assert_eq!(connect4_best_move(game, 4, 0), 2);
+                assert_eq!(connect4_place(game, 3), Connect4Result::Ok);
+                assert_eq!(connect4_place(game, 2), Connect4Result::Ok);
+                assert_eq!(connect4_status(game), Connect4Status::WhiteWon);
+                assert_eq!(connect4_place(game, 2), Connect4Result::Over);
+                assert_eq!(connect4_best_move(game, 4, 0), -1);
+                connect4_free(game);
+
+                assert_eq!(
+                    connect4_place(std::ptr::null_mut(), 0),
+                    Connect4Result::NoGame
+                );
+                connect4_free(std::ptr::null_mut());
+            }
+        }
+    }
+}
@@ -1,35 +1,49 @@
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Error {
     OutOfBounds,
     ColumnFull,
+    Over,
+    CannotPop,
+    Forbidden,
+    Occupied,
 }
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
         match self {
             Self::OutOfBounds => write!(fmt, "out of bounds"),
             Self::ColumnFull => write!(fmt, "column full"),
+            Self::Over => write!(fmt, "game over"),
+            Self::CannotPop => write!(fmt, "no token of the player to pop"),
+            Self::Forbidden => write!(fmt, "not allowed by the rules"),
+            Self::Occupied => write!(fmt, "cell taken"),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Status {
     Victory,
     Tie,
     Ongoing,
+    // The player to move ran out of time, which only a clock can tell
+    Timeout,
+    // The move lined up tokens of the other player only, which only popping can do
+    Defeat,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Token {
     White,
     Black,
 }
 
-impl std::ops::Not for Token {
+impl core::ops::Not for Token {
     type Output = Self;
 
-    #[must_use]
     fn not(self) -> Self::Output {
         match self {
             Self::White => Self::Black,
@@ -38,8 +52,8 @@ impl std::ops::Not for Token {
     }
 }
 
-impl std::fmt::Display for Token {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Token {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::White => write!(fmt, "\u{2593}\u{2593}"),
             Self::Black => write!(fmt, "\u{2591}\u{2591}"),
@@ -47,61 +61,272 @@ impl std::fmt::Display for Token {
     }
 }
 
-pub trait Game: Sized + Send + std::fmt::Display {
+// What a player does on their turn: drop a token in a column, or, in Pop Out, take one of
+// their own tokens out of the bottom of a column, or, without gravity, put a token on the
+// cell at a column and a row
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Move {
+    Drop(u8),
+    Pop(u8),
+    Place(u8, u8),
+}
+
+impl Move {
+    pub fn column(self) -> u8 {
+        match self {
+            Self::Drop(x) | Self::Pop(x) | Self::Place(x, _) => x,
+        }
+    }
+}
+
+// Columns count from 1, as they are numbered under the board, and rows from 1 at the top, pops
+// starting with a p and cells giving the column then the row: 4, p4 or 4:2
+impl core::fmt::Display for Move {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Drop(x) => write!(fmt, "{}", u16::from(*x) + 1),
+            Self::Pop(x) => write!(fmt, "p{}", u16::from(*x) + 1),
+            Self::Place(x, y) => write!(fmt, "{}:{}", u16::from(*x) + 1, u16::from(*y) + 1),
+        }
+    }
+}
+
+// Cells are also read with a comma between the column and the row, like 4,2
+impl core::str::FromStr for Move {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let index = |number: &str| {
+            number
+                .parse::<u8>()
+                .ok()
+                .and_then(|number| number.checked_sub(1))
+                .ok_or(())
+        };
+        if let Some((column, row)) = string.split_once([':', ',']) {
+            return Ok(Self::Place(index(column)?, index(row)?));
+        }
+        match string.strip_prefix('p') {
+            Some(column) => Ok(Self::Pop(index(column)?)),
+            None => Ok(Self::Drop(index(string)?)),
+        }
+    }
+}
+
+pub trait Game: Sized + Send + Sync + core::hash::Hash + core::fmt::Display {
     fn place(&self, token: Token, x: u8) -> Result<Self, Error>;
+    #[allow(dead_code)]
     fn plan(&self, token: Token, x: u8) -> Result<Status, Error>;
+    // Row, counting from the top, where a token dropped in the given column comes to rest
+    fn landing(&self, x: u8) -> Result<u8, Error>;
+    // Token at the given column and row, counting from the top
+    fn cell(&self, x: u8, y: u8) -> Option<Token>;
     fn status(&self) -> Status;
+    // Number of columns
     fn size(&self) -> u8;
+    // Number of rows
+    fn height(&self) -> u8;
+    // Number of tokens in a line needed to win
+    fn win(&self) -> u8;
+
+    // Drops in every game, pops and cells only where the rules allow them
+    fn play(&self, token: Token, action: Move) -> Result<Self, Error> {
+        match action {
+            Move::Drop(x) => self.place(token, x),
+            Move::Pop(_) => Err(Error::CannotPop),
+            Move::Place(..) => Err(Error::Forbidden),
+        }
+    }
+
+    // Whether the player to move may make the move
+    fn allows(&self, _token: Token, action: Move) -> bool {
+        self.status() == Status::Ongoing
+            && match action {
+                Move::Drop(x) => self.landing(x).is_ok(),
+                Move::Pop(_) | Move::Place(..) => false,
+            }
+    }
+
+    // Every move the player to move may make, column by column
+    fn moves(&self, token: Token) -> impl Iterator<Item = Move> + '_ {
+        let free = !self.gravity();
+        (0..self.size())
+            .flat_map(move |x| {
+                let cells = (0..self.height())
+                    .filter(move |_| free)
+                    .map(move |y| Move::Place(x, y));
+                IntoIterator::into_iter([Move::Drop(x), Move::Pop(x)]).chain(cells)
+            })
+            .filter(move |action| self.allows(token, *action))
+    }
+
+    // Whether tokens fall down their column, rather than going on any empty cell
+    fn gravity(&self) -> bool {
+        true
+    }
+
+    // Every move the player whose turn it is may make, none once the game is over
+    fn legal_moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.moves(self.side_to_move())
+    }
+
+    // Tokens in the column, none outside of the board
+    fn column_height(&self, x: u8) -> u8 {
+        match self.landing(x) {
+            Ok(y) => self.height() - y - 1,
+            Err(Error::ColumnFull) => self.height(),
+            Err(
+                Error::OutOfBounds
+                | Error::Over
+                | Error::CannotPop
+                | Error::Forbidden
+                | Error::Occupied,
+            ) => 0,
+        }
+    }
+
+    fn move_count(&self) -> usize {
+        (0..self.size())
+            .map(|x| usize::from(self.column_height(x)))
+            .sum()
+    }
+
+    // White moves first, then the players take turns
+    fn side_to_move(&self) -> Token {
+        if self.move_count() % 2 == 0 {
+            Token::White
+        } else {
+            Token::Black
+        }
+    }
+
+    // Tokens the player has taken off the board, in variants that keep them
+    fn collected(&self, _token: Token) -> Option<u8> {
+        None
+    }
+
+    // Every token on the board with its column and row, row by row from the top
+    fn tokens(&self) -> impl Iterator<Item = (u8, u8, Token)> + '_ {
+        (0..self.height()).flat_map(move |y| {
+            (0..self.size()).filter_map(move |x| self.cell(x, y).map(|token| (x, y, token)))
+        })
+    }
+}
+
+pub const WIDTH: u8 = 7;
+pub const HEIGHT: u8 = 7;
+pub const WIN: u8 = 4;
+// Tokens to collect to win Pop 10
+pub const COLLECT: u8 = 10;
+
+// Board of up to 127 columns and rows, won by lining up `win` tokens
+pub fn new(width: u8, height: u8, win: u8) -> Connect4 {
+    Connect4::new(width, height, win)
 }
 
-pub fn new() -> impl Game {
-    Connect4::new()
+// Any of the rules, for programs that let players choose them
+pub fn variant(variant: Variant, width: u8, height: u8, win: u8) -> AnyGame {
+    match variant {
+        Variant::Classic => AnyGame::Classic(Connect4::new(width, height, win)),
+        Variant::PopOut => AnyGame::PopOut(PopOut::new(width, height, win)),
+        Variant::Pop10 => AnyGame::Pop10(Pop10::new(width, height, win)),
+        Variant::Cylinder => AnyGame::Cylinder(Connect4::cylinder(width, height, win)),
+        Variant::Free => AnyGame::Free(Free::new(width, height, win)),
+    }
 }
 
-struct Connect4 {
+// Plays the moves in turns from the start, white first, returning who is to play next,
+// or the index of the move that could not be played
+pub fn replay<Game: self::Game>(
+    mut game: Game,
+    moves: &[Move],
+) -> Result<(Game, Token), (usize, Error)> {
+    let mut token = Token::White;
+    for (index, action) in moves.iter().enumerate() {
+        if game.status() != Status::Ongoing {
+            return Err((index, Error::Over));
+        }
+        game = game.play(token, *action).map_err(|e| (index, e))?;
+        token = !token;
+    }
+    Ok((game, token))
+}
+
+#[derive(Clone, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "UncheckedConnect4")
+)]
+pub struct Connect4 {
     board: Board,
     status: Status,
+    win: u8,
 }
 
 impl Connect4 {
-    fn new() -> Self {
+    fn new(width: u8, height: u8, win: u8) -> Self {
         Self {
-            board: Board::new(),
+            board: Board::new(width, height),
             status: Status::Ongoing,
+            win,
         }
     }
 
-    fn build_status(token: Token, position: &Position, board: &Board) -> Status {
-        if Self::tie(&position, &board) {
-            Status::Tie
-        } else if Self::victory(token, &position, &board) {
+    // The left and right edges of the board joined, lines going round them
+    fn cylinder(width: u8, height: u8, win: u8) -> Self {
+        let mut game = Self::new(width, height, win);
+        game.board.wrap = true;
+        game
+    }
+
+    fn build_status(&self, token: Token, position: &Position, board: &Board) -> Status {
+        if self.victory(token, position, board) {
             Status::Victory
+        } else if Self::tie(position, board) {
+            Status::Tie
         } else {
             Status::Ongoing
         }
     }
 
     fn tie(position: &Position, board: &Board) -> bool {
-        position.y == 0 && !board.cells[0].iter().any(|c| *c == Cell::Empty)
+        position.y == 0 && !board[0].contains(&Cell::Empty)
     }
 
-    fn victory(token: Token, position: &Position, board: &Board) -> bool {
-        Self::direction_score(token, position, &board, &Direction::S)
-            || Self::direction_score(token, position, &board, &Direction::E)
-            || Self::direction_score(token, position, &board, &Direction::NE)
-            || Self::direction_score(token, position, &board, &Direction::SE)
+    fn victory(&self, token: Token, position: &Position, board: &Board) -> bool {
+        self.direction_score(token, position, board, &Direction::S)
+            || self.direction_score(token, position, board, &Direction::E)
+            || self.direction_score(token, position, board, &Direction::NE)
+            || self.direction_score(token, position, board, &Direction::SE)
     }
 
     fn direction_score(
+        &self,
         token: Token,
         position: &Position,
         board: &Board,
         direction: &Direction,
     ) -> bool {
         let reverse = &direction.reverse();
-        (Self::compound_direction_score(token, position + direction, &board, direction)
-            + Self::compound_direction_score(token, position + reverse, &board, reverse))
-            >= 3
+        // On a cylinder a row could come back round to the token, so both sides together count
+        // no more than the other cells of the row
+        let cells = if direction.y == 0 {
+            board.width
+        } else {
+            board.height
+        } - 1;
+        let ahead =
+            Self::compound_direction_score(token, position + direction, board, direction, cells);
+        let behind = Self::compound_direction_score(
+            token,
+            position + reverse,
+            board,
+            reverse,
+            cells - ahead,
+        );
+        ahead + behind + 1 >= self.win
     }
 
     #[allow(clippy::needless_pass_by_value)]
@@ -110,9 +335,17 @@ impl Connect4 {
         position: Position,
         board: &Board,
         direction: &Direction,
+        cells: u8,
     ) -> u8 {
-        if board.is_token(token, &position) {
-            Self::compound_direction_score(token, &position + direction, &board, direction) + 1
+        let position = board.wrapped(&position);
+        if cells > 0 && board.is_token(token, &position) {
+            Self::compound_direction_score(
+                token,
+                &position + direction,
+                board,
+                direction,
+                cells - 1,
+            ) + 1
         } else {
             0
         }
@@ -120,129 +353,967 @@ impl Connect4 {
 
     #[allow(clippy::cast_possible_wrap)]
     fn fall_position(&self, x: u8) -> Result<Position, Error> {
-        if x >= Board::size() {
+        if x >= self.board.width {
             return Err(Error::OutOfBounds);
         }
 
         let position = self.fall_position_height(Position { x: x as i8, y: -1 });
 
-        if position.y < 0 {
-            Err(Error::ColumnFull)
-        } else {
-            Ok(position)
+        if position.y < 0 {
+            Err(Error::ColumnFull)
+        } else {
+            Ok(position)
+        }
+    }
+
+    fn fall_position_height(&self, position: Position) -> Position {
+        let floor = &position + &Direction::S;
+        if Cell::Empty == self.board.cell(&floor) {
+            self.fall_position_height(floor)
+        } else {
+            position
+        }
+    }
+}
+
+impl Game for Connect4 {
+    #[allow(clippy::cast_sign_loss)]
+    fn place(&self, token: Token, x: u8) -> Result<Self, Error> {
+        let position = self.fall_position(x)?;
+        Ok({
+            let mut board = self.board.clone();
+            board[position.y as usize][position.x as usize] = Cell::Token(token);
+            let status = self.build_status(token, &position, &board);
+
+            Self {
+                board,
+                status,
+                win: self.win,
+            }
+        })
+    }
+
+    fn plan(&self, token: Token, x: u8) -> Result<Status, Error> {
+        let position = self.fall_position(x)?;
+        Ok(self.build_status(token, &position, &self.board))
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn landing(&self, x: u8) -> Result<u8, Error> {
+        self.fall_position(x).map(|position| position.y as u8)
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn cell(&self, x: u8, y: u8) -> Option<Token> {
+        match self.board.cell(&Position {
+            x: x as i8,
+            y: y as i8,
+        }) {
+            Cell::Token(token) => Some(token),
+            Cell::Empty | Cell::OutOfBounds => None,
+        }
+    }
+
+    fn status(&self) -> Status {
+        self.status
+    }
+
+    fn size(&self) -> u8 {
+        self.board.width
+    }
+
+    fn height(&self) -> u8 {
+        self.board.height
+    }
+
+    fn win(&self) -> u8 {
+        self.win
+    }
+}
+
+impl core::fmt::Display for Connect4 {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for row in self.board.cells.chunks(usize::from(self.board.width)) {
+            for cell in row {
+                write!(fmt, "|{cell}")?;
+            }
+            writeln!(fmt, "|")?;
+        }
+
+        for _ in 0..self.board.width {
+            write!(fmt, "---")?;
+        }
+        writeln!(fmt, "-")?;
+
+        for i in 0..self.board.width {
+            write!(fmt, " {:2}", i + 1)?;
+        }
+        writeln!(fmt)
+    }
+}
+
+// Connect 4 where a player may also take one of their own tokens out of the bottom row, the
+// tokens above it falling down. When that lines up tokens of both players, the one who popped
+// wins. A full board is no draw while the player to move can pop, a position coming up for the
+// third time is
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PopOut {
+    game: Connect4,
+    turn: Token,
+    // Positions so far, which are not kept when serialized
+    #[cfg_attr(feature = "serde", serde(skip))]
+    seen: Option<alloc::sync::Arc<Seen>>,
+}
+
+// A position and who moved to it, shared by the games that followed
+struct Seen {
+    board: Board,
+    token: Token,
+    previous: Option<alloc::sync::Arc<Seen>>,
+}
+
+impl Seen {
+    // Times the player moved to the board before
+    fn count(seen: Option<&Self>, token: Token, board: &Board) -> usize {
+        core::iter::successors(seen, |seen| seen.previous.as_deref())
+            .filter(|seen| seen.token == token && seen.board == *board)
+            .count()
+    }
+}
+
+impl PopOut {
+    pub fn new(width: u8, height: u8, win: u8) -> Self {
+        let game = Connect4::new(width, height, win);
+        // As if black had just moved, so that emptying the board again counts
+        let seen = Seen {
+            board: game.board.clone(),
+            token: Token::Black,
+            previous: None,
+        };
+        Self {
+            game,
+            turn: Token::White,
+            seen: Some(alloc::sync::Arc::new(seen)),
+        }
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn pop(&self, token: Token, x: u8) -> Result<Self, Error> {
+        let height = self.game.board.height;
+        if x >= self.game.board.width {
+            return Err(Error::OutOfBounds);
+        }
+        if self.cell(x, height - 1) != Some(token) {
+            return Err(Error::CannotPop);
+        }
+
+        let mut board = self.game.board.clone();
+        let column = usize::from(x);
+        for y in (1..usize::from(height)).rev() {
+            board[y][column] = board[y - 1][column];
+        }
+        board[0][column] = Cell::Empty;
+
+        // Only the tokens of the column moved, so any line goes through one of them
+        let lined = |token: Token| {
+            (0..height as i8).any(|y| {
+                let position = Position { x: x as i8, y };
+                board.is_token(token, &position) && self.game.victory(token, &position, &board)
+            })
+        };
+        let status = if lined(token) {
+            Status::Victory
+        } else if lined(!token) {
+            Status::Defeat
+        } else {
+            Status::Ongoing
+        };
+
+        Ok(self.next(
+            token,
+            Connect4 {
+                board,
+                status,
+                win: self.game.win,
+            },
+        ))
+    }
+
+    // The game after the move of the token, keeping track of the positions
+    fn next(&self, token: Token, mut game: Connect4) -> Self {
+        let bottom = usize::from(game.board.height - 1);
+        if game.status == Status::Tie && game.board[bottom].contains(&Cell::Token(!token)) {
+            game.status = Status::Ongoing;
+        }
+
+        let repeated = Seen::count(self.seen.as_deref(), token, &game.board);
+        if game.status == Status::Ongoing && repeated >= 2 {
+            game.status = Status::Tie;
+        }
+
+        let seen = Seen {
+            board: game.board.clone(),
+            token,
+            previous: self.seen.clone(),
+        };
+        Self {
+            game,
+            turn: !token,
+            seen: Some(alloc::sync::Arc::new(seen)),
+        }
+    }
+}
+
+impl Game for PopOut {
+    fn place(&self, token: Token, x: u8) -> Result<Self, Error> {
+        self.game.place(token, x).map(|game| self.next(token, game))
+    }
+
+    fn plan(&self, token: Token, x: u8) -> Result<Status, Error> {
+        self.place(token, x).map(|game| game.status())
+    }
+
+    fn landing(&self, x: u8) -> Result<u8, Error> {
+        self.game.landing(x)
+    }
+
+    fn cell(&self, x: u8, y: u8) -> Option<Token> {
+        self.game.cell(x, y)
+    }
+
+    fn status(&self) -> Status {
+        self.game.status
+    }
+
+    fn size(&self) -> u8 {
+        self.game.size()
+    }
+
+    fn height(&self) -> u8 {
+        self.game.height()
+    }
+
+    fn win(&self) -> u8 {
+        self.game.win
+    }
+
+    fn play(&self, token: Token, action: Move) -> Result<Self, Error> {
+        match action {
+            Move::Drop(x) => self.place(token, x),
+            Move::Pop(x) => self.pop(token, x),
+            Move::Place(..) => Err(Error::Forbidden),
+        }
+    }
+
+    fn allows(&self, token: Token, action: Move) -> bool {
+        self.status() == Status::Ongoing
+            && match action {
+                Move::Drop(x) => self.landing(x).is_ok(),
+                Move::Pop(x) => x < self.size() && self.cell(x, self.height() - 1) == Some(token),
+                Move::Place(..) => false,
+            }
+    }
+
+    // Pops take tokens off the board, so the turn is counted instead
+    fn side_to_move(&self) -> Token {
+        self.turn
+    }
+}
+
+// Two games with the same tokens are the same position, however they got there
+impl core::hash::Hash for PopOut {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.game.hash(state);
+    }
+}
+
+impl core::fmt::Display for PopOut {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.game.fmt(fmt)
+    }
+}
+
+// Connect 4 in two phases. The players first fill the board in turns, row by row from the
+// bottom, lines counting for nothing. Then each turn a player pops one of their own tokens: one
+// that was part of a line of theirs is collected, any other goes back on top of its column. A
+// completed line through the bottom row must be taken from before anything else is popped. The
+// first to collect 10 tokens wins, and so does a player who leaves the other nothing to pop. A
+// position coming up for the third time is a draw
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pop10 {
+    game: Connect4,
+    // Tokens collected by white and black
+    collected: [u8; 2],
+    turn: Token,
+    // Positions so far, which are not kept when serialized
+    #[cfg_attr(feature = "serde", serde(skip))]
+    seen: Option<alloc::sync::Arc<Seen>>,
+}
+
+impl Pop10 {
+    pub fn new(width: u8, height: u8, win: u8) -> Self {
+        Self {
+            game: Connect4::new(width, height, win),
+            collected: [0, 0],
+            turn: Token::White,
+            seen: None,
+        }
+    }
+
+    fn index(token: Token) -> usize {
+        match token {
+            Token::White => 0,
+            Token::Black => 1,
+        }
+    }
+
+    // Whether the board is still being filled, as no token has been collected yet
+    fn setup(&self) -> bool {
+        self.collected == [0, 0] && self.game.board[0].contains(&Cell::Empty)
+    }
+
+    // Whether the token at the bottom of the column is part of a line of the player
+    #[allow(clippy::cast_possible_wrap)]
+    fn lined(&self, token: Token, x: u8) -> bool {
+        let position = Position {
+            x: x as i8,
+            y: self.game.board.height as i8 - 1,
+        };
+        self.game.board.is_token(token, &position)
+            && self.game.victory(token, &position, &self.game.board)
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn fill(&self, token: Token, x: u8) -> Result<Self, Error> {
+        if !self.setup() {
+            return Err(Error::Forbidden);
+        }
+        let position = self.game.fall_position(x)?;
+        // Rows fill up from the bottom
+        let lower = (0..self.size()).any(|other| {
+            self.game
+                .landing(other)
+                .is_ok_and(|y| i16::from(y) > i16::from(position.y))
+        });
+        if lower {
+            return Err(Error::Forbidden);
+        }
+
+        let mut board = self.game.board.clone();
+        board[position.y as usize][position.x as usize] = Cell::Token(token);
+        Ok(self.next(token, board, self.collected))
+    }
+
+    fn pop(&self, token: Token, x: u8) -> Result<Self, Error> {
+        let height = self.game.board.height;
+        if x >= self.game.board.width {
+            return Err(Error::OutOfBounds);
+        }
+        if self.setup() || self.cell(x, height - 1) != Some(token) {
+            return Err(Error::CannotPop);
+        }
+        let collect = self.lined(token, x);
+        if !collect && (0..self.size()).any(|other| self.lined(token, other)) {
+            return Err(Error::Forbidden);
+        }
+
+        let mut board = self.game.board.clone();
+        let column = usize::from(x);
+        for y in (1..usize::from(height)).rev() {
+            board[y][column] = board[y - 1][column];
+        }
+        board[0][column] = Cell::Empty;
+        let mut collected = self.collected;
+        if collect {
+            collected[Self::index(token)] += 1;
+        } else if let Some(y) = (0..usize::from(height)).rfind(|y| board[*y][column] == Cell::Empty)
+        {
+            board[y][column] = Cell::Token(token);
+        }
+        Ok(self.next(token, board, collected))
+    }
+
+    // The game after the move of the token, keeping track of the positions
+    fn next(&self, token: Token, board: Board, collected: [u8; 2]) -> Self {
+        let bottom = usize::from(board.height - 1);
+        let playing = !board[0].contains(&Cell::Empty) || collected != [0, 0];
+        let status = if collected[Self::index(token)] >= COLLECT
+            || (playing && !board[bottom].contains(&Cell::Token(!token)))
+        {
+            Status::Victory
+        } else if Seen::count(self.seen.as_deref(), token, &board) >= 2 {
+            Status::Tie
+        } else {
+            Status::Ongoing
+        };
+
+        let seen = Seen {
+            board: board.clone(),
+            token,
+            previous: self.seen.clone(),
+        };
+        Self {
+            game: Connect4 {
+                board,
+                status,
+                win: self.game.win,
+            },
+            collected,
+            turn: !token,
+            seen: Some(alloc::sync::Arc::new(seen)),
+        }
+    }
+}
+
+impl Game for Pop10 {
+    fn place(&self, token: Token, x: u8) -> Result<Self, Error> {
+        self.fill(token, x)
+    }
+
+    fn plan(&self, token: Token, x: u8) -> Result<Status, Error> {
+        self.fill(token, x).map(|game| game.status())
+    }
+
+    fn landing(&self, x: u8) -> Result<u8, Error> {
+        self.game.landing(x)
+    }
+
+    fn cell(&self, x: u8, y: u8) -> Option<Token> {
+        self.game.cell(x, y)
+    }
+
+    fn status(&self) -> Status {
+        self.game.status
+    }
+
+    fn size(&self) -> u8 {
+        self.game.size()
+    }
+
+    fn height(&self) -> u8 {
+        self.game.height()
+    }
+
+    fn win(&self) -> u8 {
+        self.game.win
+    }
+
+    fn play(&self, token: Token, action: Move) -> Result<Self, Error> {
+        match action {
+            Move::Drop(x) => self.fill(token, x),
+            Move::Pop(x) => self.pop(token, x),
+            Move::Place(..) => Err(Error::Forbidden),
+        }
+    }
+
+    fn allows(&self, token: Token, action: Move) -> bool {
+        self.status() == Status::Ongoing && self.play(token, action).is_ok()
+    }
+
+    // Pops keep the number of tokens, so the turn is counted instead
+    fn side_to_move(&self) -> Token {
+        self.turn
+    }
+
+    fn collected(&self, token: Token) -> Option<u8> {
+        Some(self.collected[Self::index(token)])
+    }
+}
+
+impl core::hash::Hash for Pop10 {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.game.hash(state);
+        self.collected.hash(state);
+    }
+}
+
+impl core::fmt::Display for Pop10 {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.game.fmt(fmt)?;
+        writeln!(
+            fmt,
+            "Collected: {} {}  {} {}",
+            Token::White,
+            self.collected[0],
+            Token::Black,
+            self.collected[1]
+        )
+    }
+}
+
+// Tokens put on any empty cell rather than dropped, for k in a row games like gomoku
+#[derive(Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Free {
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "floating"))]
+    game: Connect4,
+}
+
+impl Free {
+    pub fn new(width: u8, height: u8, win: u8) -> Self {
+        Self {
+            game: Connect4::new(width, height, win),
+        }
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn put(&self, token: Token, x: u8, y: u8) -> Result<Self, Error> {
+        if x >= self.size() || y >= self.height() {
+            return Err(Error::OutOfBounds);
+        }
+        if self.cell(x, y).is_some() {
+            return Err(Error::Occupied);
+        }
+
+        let position = Position {
+            x: x as i8,
+            y: y as i8,
+        };
+        let mut board = self.game.board.clone();
+        board[usize::from(y)][usize::from(x)] = Cell::Token(token);
+        let status = if self.game.victory(token, &position, &board) {
+            Status::Victory
+        } else if board.cells.contains(&Cell::Empty) {
+            Status::Ongoing
+        } else {
+            Status::Tie
+        };
+        Ok(Self {
+            game: Connect4 {
+                board,
+                status,
+                win: self.game.win,
+            },
+        })
+    }
+}
+
+// Nothing falls, so tokens are never dropped
+impl Game for Free {
+    fn place(&self, _token: Token, _x: u8) -> Result<Self, Error> {
+        Err(Error::Forbidden)
+    }
+
+    fn plan(&self, _token: Token, _x: u8) -> Result<Status, Error> {
+        Err(Error::Forbidden)
+    }
+
+    fn landing(&self, _x: u8) -> Result<u8, Error> {
+        Err(Error::Forbidden)
+    }
+
+    fn cell(&self, x: u8, y: u8) -> Option<Token> {
+        self.game.cell(x, y)
+    }
+
+    fn status(&self) -> Status {
+        self.game.status
+    }
+
+    fn size(&self) -> u8 {
+        self.game.size()
+    }
+
+    fn height(&self) -> u8 {
+        self.game.height()
+    }
+
+    fn win(&self) -> u8 {
+        self.game.win
+    }
+
+    fn play(&self, token: Token, action: Move) -> Result<Self, Error> {
+        match action {
+            Move::Drop(_) => Err(Error::Forbidden),
+            Move::Pop(_) => Err(Error::CannotPop),
+            Move::Place(x, y) => self.put(token, x, y),
+        }
+    }
+
+    fn allows(&self, _token: Token, action: Move) -> bool {
+        self.status() == Status::Ongoing
+            && match action {
+                Move::Drop(_) | Move::Pop(_) => false,
+                Move::Place(x, y) => {
+                    x < self.size() && y < self.height() && self.cell(x, y).is_none()
+                }
+            }
+    }
+
+    fn gravity(&self) -> bool {
+        false
+    }
+
+    // Tokens can be anywhere in the column, with gaps between them
+    fn column_height(&self, x: u8) -> u8 {
+        (0..self.height())
+            .map(|y| u8::from(self.cell(x, y).is_some()))
+            .sum()
+    }
+}
+
+// Rows are numbered on the right, from the top, as moves name them
+impl core::fmt::Display for Free {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let board = &self.game.board;
+        for (y, row) in board.cells.chunks(usize::from(board.width)).enumerate() {
+            for cell in row {
+                write!(fmt, "|{cell}")?;
+            }
+            writeln!(fmt, "| {}", y + 1)?;
+        }
+
+        for _ in 0..board.width {
+            write!(fmt, "---")?;
+        }
+        writeln!(fmt, "-")?;
+
+        for i in 0..board.width {
+            write!(fmt, " {:2}", i + 1)?;
+        }
+        writeln!(fmt)
+    }
+}
+
+// The rules to play by
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Variant {
+    Classic,
+    PopOut,
+    Pop10,
+    Cylinder,
+    Free,
+}
+
+impl core::str::FromStr for Variant {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "classic" => Ok(Self::Classic),
+            "popout" => Ok(Self::PopOut),
+            "pop10" => Ok(Self::Pop10),
+            "cylinder" => Ok(Self::Cylinder),
+            "free" => Ok(Self::Free),
+            _ => Err(()),
+        }
+    }
+}
+
+impl core::fmt::Display for Variant {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Classic => write!(fmt, "classic"),
+            Self::PopOut => write!(fmt, "popout"),
+            Self::Pop10 => write!(fmt, "pop10"),
+            Self::Cylinder => write!(fmt, "cylinder"),
+            Self::Free => write!(fmt, "free"),
+        }
+    }
+}
+
+// A game of any variant, chosen as the program runs
+#[derive(Clone, Hash)]
+pub enum AnyGame {
+    Classic(Connect4),
+    PopOut(PopOut),
+    Pop10(Pop10),
+    Cylinder(Connect4),
+    Free(Free),
+}
+
+// The same code for the game of every variant
+macro_rules! each {
+    ($game:expr, $inner:ident => $body:expr) => {
+        match $game {
+            AnyGame::Classic($inner) => $body,
+            AnyGame::PopOut($inner) => $body,
+            AnyGame::Pop10($inner) => $body,
+            AnyGame::Cylinder($inner) => $body,
+            AnyGame::Free($inner) => $body,
+        }
+    };
+}
+
+impl AnyGame {
+    pub fn variant(&self) -> Variant {
+        match self {
+            Self::Classic(_) => Variant::Classic,
+            Self::PopOut(_) => Variant::PopOut,
+            Self::Pop10(_) => Variant::Pop10,
+            Self::Cylinder(_) => Variant::Cylinder,
+            Self::Free(_) => Variant::Free,
         }
     }
+}
 
-    fn fall_position_height(&self, position: Position) -> Position {
-        let floor = &position + &Direction::S;
-        if Cell::Empty == self.board.cell(&floor) {
-            self.fall_position_height(floor)
+impl From<Connect4> for AnyGame {
+    fn from(game: Connect4) -> Self {
+        if game.board.wrap {
+            Self::Cylinder(game)
         } else {
-            position
+            Self::Classic(game)
         }
     }
 }
 
-impl Game for Connect4 {
-    #[allow(clippy::cast_sign_loss)]
-    fn place(&self, token: Token, x: u8) -> Result<Self, Error> {
-        let position = self.fall_position(x)?;
-        Ok({
-            let mut cells = self.board.cells;
-            cells[position.y as usize][position.x as usize] = Cell::Token(token);
-            let board = Board { cells };
-            let status = Self::build_status(token, &position, &board);
+impl From<PopOut> for AnyGame {
+    fn from(game: PopOut) -> Self {
+        Self::PopOut(game)
+    }
+}
 
-            Self { board, status }
-        })
+impl From<Pop10> for AnyGame {
+    fn from(game: Pop10) -> Self {
+        Self::Pop10(game)
+    }
+}
+
+impl From<Free> for AnyGame {
+    fn from(game: Free) -> Self {
+        Self::Free(game)
+    }
+}
+
+impl Game for AnyGame {
+    fn place(&self, token: Token, x: u8) -> Result<Self, Error> {
+        each!(self, game => game.place(token, x).map(Self::from))
     }
 
     fn plan(&self, token: Token, x: u8) -> Result<Status, Error> {
-        let position = self.fall_position(x)?;
-        Ok(Self::build_status(token, &position, &self.board))
+        each!(self, game => game.plan(token, x))
+    }
+
+    fn landing(&self, x: u8) -> Result<u8, Error> {
+        each!(self, game => game.landing(x))
+    }
+
+    fn cell(&self, x: u8, y: u8) -> Option<Token> {
+        each!(self, game => game.cell(x, y))
     }
 
     fn status(&self) -> Status {
-        self.status
+        each!(self, game => game.status())
     }
 
     fn size(&self) -> u8 {
-        Board::size()
+        each!(self, game => game.size())
     }
-}
 
-impl std::fmt::Display for Connect4 {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in &self.board.cells {
-            for cell in row {
-                write!(fmt, "|{}", cell)?;
-            }
-            writeln!(fmt, "|")?;
-        }
+    fn height(&self) -> u8 {
+        each!(self, game => game.height())
+    }
 
-        for _ in 0..Board::size() {
-            write!(fmt, "---")?;
-        }
-        writeln!(fmt, "-")?;
+    fn win(&self) -> u8 {
+        each!(self, game => game.win())
+    }
 
-        for i in 0..Board::size() {
-            write!(fmt, " {:2}", i + 1)?;
-        }
-        writeln!(fmt)
+    fn play(&self, token: Token, action: Move) -> Result<Self, Error> {
+        each!(self, game => game.play(token, action).map(Self::from))
+    }
+
+    fn allows(&self, token: Token, action: Move) -> bool {
+        each!(self, game => game.allows(token, action))
+    }
+
+    fn gravity(&self) -> bool {
+        each!(self, game => game.gravity())
+    }
+
+    fn column_height(&self, x: u8) -> u8 {
+        each!(self, game => game.column_height(x))
+    }
+
+    fn side_to_move(&self) -> Token {
+        each!(self, game => game.side_to_move())
+    }
+
+    fn collected(&self, token: Token) -> Option<u8> {
+        each!(self, game => game.collected(token))
+    }
+}
+
+impl core::fmt::Display for AnyGame {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        each!(self, game => game.fmt(fmt))
     }
 }
 
+// Cells are stored row by row, from the top
+#[derive(Clone, Hash, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "UncheckedBoard")
+)]
 struct Board {
-    cells: [[Cell; Self::usize()]; Self::usize()],
+    width: u8,
+    height: u8,
+    // Whether the last column is next to the first
+    wrap: bool,
+    cells: alloc::vec::Vec<Cell>,
 }
 
 impl Board {
-    fn new() -> Self {
+    fn new(width: u8, height: u8) -> Self {
         Self {
-            cells: [[Cell::Empty; Self::usize()]; Self::usize()],
+            width,
+            height,
+            wrap: false,
+            cells: alloc::vec![Cell::Empty; usize::from(width) * usize::from(height)],
+        }
+    }
+
+    // The same cell with its column back on the board, if the board wraps
+    #[allow(clippy::cast_possible_wrap)]
+    fn wrapped(&self, position: &Position) -> Position {
+        Position {
+            x: if self.wrap {
+                position.x.rem_euclid(self.width as i8)
+            } else {
+                position.x
+            },
+            y: position.y,
         }
     }
 
     #[allow(clippy::cast_sign_loss)]
     fn cell(&self, position: &Position) -> Cell {
+        let position = &self.wrapped(position);
         if position.x < 0
             || position.y < 0
-            || position.x as u8 >= Self::size()
-            || position.y as u8 >= Self::size()
+            || position.x as u8 >= self.width
+            || position.y as u8 >= self.height
         {
             Cell::OutOfBounds
         } else {
-            self.cells[position.y as usize][position.x as usize]
+            self[position.y as usize][position.x as usize]
         }
     }
 
     fn is_token(&self, token: Token, position: &Position) -> bool {
-        self.cell(&position) == Cell::Token(token)
+        self.cell(position) == Cell::Token(token)
+    }
+}
+
+// A board as it is read, before checking that the cells fill it
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct UncheckedBoard {
+    width: u8,
+    height: u8,
+    #[serde(default)]
+    wrap: bool,
+    cells: alloc::vec::Vec<Cell>,
+}
+
+#[cfg(feature = "serde")]
+impl core::convert::TryFrom<UncheckedBoard> for Board {
+    type Error = alloc::string::String;
+
+    fn try_from(cells: UncheckedBoard) -> Result<Self, Self::Error> {
+        let board = Self {
+            width: cells.width,
+            height: cells.height,
+            wrap: cells.wrap,
+            cells: cells.cells,
+        };
+        let sides = [board.width, board.height];
+        if sides
+            .iter()
+            .any(|side| *side == 0 || i8::try_from(*side).is_err())
+        {
+            return Err(alloc::string::String::from("sides must be from 1 to 127"));
+        }
+        if board.cells.len() != usize::from(board.width) * usize::from(board.height) {
+            return Err(alloc::format!(
+                "{} cells do not fill a board of {}x{}",
+                board.cells.len(),
+                board.width,
+                board.height
+            ));
+        }
+        if board.cells.contains(&Cell::OutOfBounds) {
+            return Err(alloc::string::String::from("cells cannot be out of bounds"));
+        }
+        Ok(board)
+    }
+}
+
+// A game as it is read, before checking that no token floats, which they may in free games
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct UncheckedConnect4 {
+    board: Board,
+    status: Status,
+    win: u8,
+}
+
+#[cfg(feature = "serde")]
+impl core::convert::TryFrom<UncheckedConnect4> for Connect4 {
+    type Error = alloc::string::String;
+
+    fn try_from(game: UncheckedConnect4) -> Result<Self, Self::Error> {
+        let board = &game.board;
+        for y in 1..usize::from(board.height) {
+            for x in 0..usize::from(board.width) {
+                if board[y][x] == Cell::Empty && board[y - 1][x] != Cell::Empty {
+                    return Err(alloc::format!("the token in column {} floats", x + 1));
+                }
+            }
+        }
+        Ok(Self {
+            board: game.board,
+            status: game.status,
+            win: game.win,
+        })
     }
+}
+
+#[cfg(feature = "serde")]
+fn floating<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Connect4, D::Error> {
+    let game = <UncheckedConnect4 as serde::Deserialize>::deserialize(deserializer)?;
+    Ok(Connect4 {
+        board: game.board,
+        status: game.status,
+        win: game.win,
+    })
+}
+
+// Indexing a board gives a row
+impl core::ops::Index<usize> for Board {
+    type Output = [Cell];
 
-    const fn usize() -> usize {
-        7
+    fn index(&self, y: usize) -> &[Cell] {
+        let width = usize::from(self.width);
+        &self.cells[y * width..(y + 1) * width]
     }
+}
 
-    // TODO: Make dynamic
-    const fn size() -> u8 {
-        7
+impl core::ops::IndexMut<usize> for Board {
+    fn index_mut(&mut self, y: usize) -> &mut [Cell] {
+        let width = usize::from(self.width);
+        &mut self.cells[y * width..(y + 1) * width]
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Cell {
     Empty,
     OutOfBounds,
     Token(Token),
 }
 
-impl std::fmt::Display for Cell {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Cell {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Empty => write!(fmt, "  "),
             Self::OutOfBounds => write!(fmt, ""),
-            Self::Token(p) => write!(fmt, "{}", p),
+            Self::Token(p) => write!(fmt, "{p}"),
         }
     }
 }
@@ -253,10 +1324,9 @@ struct Position {
     y: i8,
 }
 
-impl std::ops::Add<&Direction> for &Position {
+impl core::ops::Add<&Direction> for &Position {
     type Output = Position;
 
-    #[must_use]
     fn add(self, direction: &Direction) -> Position {
         Position {
             x: self.x + direction.x,
@@ -288,12 +1358,16 @@ struct Direction {
 
 #[cfg(test)]
 mod tests {
+    fn drops(columns: &[u8]) -> Vec<super::Move> {
+        columns.iter().copied().map(super::Move::Drop).collect()
+    }
+
     mod game {
         use super::super::*;
 
         #[test]
         fn place() {
-            let mut game = Connect4::new();
+            let mut game = Connect4::new(7, 7, 4);
             game = game.place(Token::Black, 1).unwrap();
             assert_eq!(game.status(), Status::Ongoing);
             game = game.place(Token::White, 2).unwrap();
@@ -318,9 +1392,46 @@ mod tests {
             assert_eq!(game.status(), Status::Victory);
         }
 
+        #[test]
+        fn queries() {
+            let mut game = Connect4::new(3, 2, 3);
+            assert_eq!(
+                game.legal_moves().collect::<Vec<_>>(),
+                vec![Move::Drop(0), Move::Drop(1), Move::Drop(2)]
+            );
+            assert_eq!(game.side_to_move(), Token::White);
+
+            game = game.place(Token::White, 1).unwrap();
+            game = game.place(Token::Black, 1).unwrap();
+            game = game.place(Token::White, 2).unwrap();
+            assert_eq!(
+                game.legal_moves().collect::<Vec<_>>(),
+                vec![Move::Drop(0), Move::Drop(2)]
+            );
+            assert_eq!(
+                (0..4).map(|x| game.column_height(x)).collect::<Vec<_>>(),
+                vec![0, 2, 1, 0]
+            );
+            assert_eq!(game.move_count(), 3);
+            assert_eq!(game.side_to_move(), Token::Black);
+            assert_eq!(
+                game.tokens().collect::<Vec<_>>(),
+                vec![
+                    (1, 0, Token::Black),
+                    (1, 1, Token::White),
+                    (2, 1, Token::White)
+                ]
+            );
+
+            game = game.place(Token::Black, 2).unwrap();
+            game = game.place(Token::White, 0).unwrap();
+            assert_eq!(game.status(), Status::Victory);
+            assert_eq!(game.legal_moves().count(), 0);
+        }
+
         #[test]
         fn place_errors() {
-            let mut game = Connect4::new();
+            let mut game = Connect4::new(7, 7, 4);
 
             game = game.place(Token::White, 3).unwrap();
             game = game.place(Token::White, 3).unwrap();
@@ -342,54 +1453,468 @@ mod tests {
 
         #[test]
         fn victory() {
-            let mut game = Connect4::new();
+            let mut game = Connect4::new(7, 7, 4);
+
+            game.board[6][2] = Cell::Token(Token::Black);
+            game.board[5][2] = Cell::Token(Token::Black);
+            game.board[4][2] = Cell::Token(Token::Black);
+            game.board[3][2] = Cell::Token(Token::White);
+
+            game.board[0][0] = Cell::Token(Token::White);
+            game.board[1][0] = Cell::Token(Token::White);
+
+            game.board[6][6] = Cell::Token(Token::Black);
+            game.board[5][5] = Cell::Token(Token::Black);
+            game.board[4][4] = Cell::Token(Token::Black);
+            game.board[4][5] = Cell::Token(Token::Black);
+
+            assert!(!game.victory(Token::Black, &Position { x: 2, y: 5 }, &game.board));
+            assert!(!game.victory(Token::White, &Position { x: 0, y: 1 }, &game.board));
+            assert!(!game.victory(Token::Black, &Position { x: 5, y: 5 }, &game.board));
+
+            game.board[3][3] = Cell::Token(Token::Black);
+            assert!(!game.victory(Token::White, &Position { x: 5, y: 5 }, &game.board));
+            assert!(game.victory(Token::Black, &Position { x: 5, y: 5 }, &game.board));
+        }
+
+        #[test]
+        fn fall_position() {
+            let mut game = Connect4::new(7, 7, 4);
+            game.board[3][2] = Cell::Token(Token::Black);
+            game.board[6][4] = Cell::Token(Token::White);
+
+            assert_eq!(game.fall_position(0).unwrap(), Position { x: 0, y: 6 });
+            assert_eq!(game.fall_position(2).unwrap(), Position { x: 2, y: 2 });
+            assert_eq!(game.fall_position(4).unwrap(), Position { x: 4, y: 5 });
+        }
+
+        #[test]
+        fn landing() {
+            let mut game = Connect4::new(7, 7, 4);
+            game.board[3][2] = Cell::Token(Token::Black);
+            for y in 0..7 {
+                game.board[y][5] = Cell::Token(Token::White);
+            }
 
-            game.board.cells[6][2] = Cell::Token(Token::Black);
-            game.board.cells[5][2] = Cell::Token(Token::Black);
-            game.board.cells[4][2] = Cell::Token(Token::Black);
-            game.board.cells[3][2] = Cell::Token(Token::White);
+            assert_eq!(game.landing(0), Ok(6));
+            assert_eq!(game.landing(2), Ok(2));
+            assert_eq!(game.landing(5), Err(Error::ColumnFull));
+            assert_eq!(game.landing(7), Err(Error::OutOfBounds));
+        }
+
+        #[test]
+        fn dimensions() {
+            let mut game = Connect4::new(5, 3, 3);
+            assert_eq!(game.landing(4), Ok(2));
+            assert_eq!(game.landing(5), Err(Error::OutOfBounds));
+
+            game = game.place(Token::White, 0).unwrap();
+            game = game.place(Token::White, 1).unwrap();
+            assert_eq!(game.status(), Status::Ongoing);
+            game = game.place(Token::White, 2).unwrap();
+            assert_eq!(game.status(), Status::Victory);
+
+            let mut game = Connect4::new(5, 3, 3);
+            for _ in 0..3 {
+                game = game.place(Token::Black, 4).unwrap();
+            }
+            assert_eq!(game.status(), Status::Victory);
+            assert_eq!(game.place(Token::Black, 4).err(), Some(Error::ColumnFull));
+            assert_eq!(game.to_string().lines().count(), 5);
+        }
+    }
 
-            game.board.cells[0][0] = Cell::Token(Token::White);
-            game.board.cells[1][0] = Cell::Token(Token::White);
+    mod replay {
+        use super::super::*;
+        use super::drops;
 
-            game.board.cells[6][6] = Cell::Token(Token::Black);
-            game.board.cells[5][5] = Cell::Token(Token::Black);
-            game.board.cells[4][4] = Cell::Token(Token::Black);
-            game.board.cells[4][5] = Cell::Token(Token::Black);
+        #[test]
+        fn turns() {
+            let (game, token) = replay(Connect4::new(7, 6, 4), &drops(&[3, 3, 4])).unwrap();
+            assert_eq!(token, Token::Black);
+            assert_eq!(game.cell(3, 5), Some(Token::White));
+            assert_eq!(game.cell(3, 4), Some(Token::Black));
+            assert_eq!(game.cell(4, 5), Some(Token::White));
+        }
 
+        #[test]
+        fn errors() {
+            let game = || Connect4::new(7, 6, 4);
+            assert_eq!(
+                replay(game(), &drops(&[3, 9])).err(),
+                Some((1, Error::OutOfBounds))
+            );
             assert_eq!(
-                Connect4::victory(Token::Black, &Position { x: 2, y: 5 }, &game.board),
-                false
+                replay(game(), &drops(&[0, 0, 0, 0, 0, 0, 0])).err(),
+                Some((6, Error::ColumnFull))
             );
             assert_eq!(
-                Connect4::victory(Token::White, &Position { x: 0, y: 1 }, &game.board),
-                false
+                replay(game(), &drops(&[0, 1, 0, 1, 0, 1, 0, 1])).err(),
+                Some((7, Error::Over))
             );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    mod serde {
+        use super::super::*;
+        use super::drops;
+
+        #[test]
+        fn round_trip() {
+            let (game, _) = replay(new(4, 3, 3), &drops(&[0, 1, 0, 1, 0])).unwrap();
+            let json = serde_json::to_string(&game).unwrap();
+            assert!(json.contains(r#""width":4,"height":3"#));
+            assert!(json.contains(r#""status":"Victory""#));
+
+            let read = serde_json::from_str::<Connect4>(&json).unwrap();
+            assert_eq!(read.to_string(), game.to_string());
+            assert_eq!(read.status(), Status::Victory);
+            assert_eq!(serde_json::to_string(&Token::Black).unwrap(), r#""Black""#);
             assert_eq!(
-                Connect4::victory(Token::Black, &Position { x: 5, y: 5 }, &game.board),
-                false
+                serde_json::from_str::<Error>(r#""ColumnFull""#).unwrap(),
+                Error::ColumnFull
+            );
+
+            // Tokens placed anywhere are read back where they are
+            let game = Free::new(3, 3, 3)
+                .play(Token::White, Move::Place(0, 0))
+                .unwrap();
+            let read = serde_json::from_str::<Free>(&serde_json::to_string(&game).unwrap());
+            assert_eq!(read.unwrap().to_string(), game.to_string());
+        }
+
+        #[test]
+        fn invalid_boards() {
+            let game = |cells: &str| {
+                format!(
+                    r#"{{"board":{{"width":2,"height":2,"cells":[{cells}]}},"status":"Ongoing","win":2}}"#
+                )
+            };
+            assert!(
+                serde_json::from_str::<Connect4>(&game(r#""Empty","Empty","Empty","Empty""#))
+                    .is_ok()
             );
+            assert!(serde_json::from_str::<Connect4>(&game(r#""Empty","Empty","Empty""#)).is_err());
+            assert!(serde_json::from_str::<Connect4>(&game(
+                r#"{"Token":"White"},"Empty","Empty","Empty""#
+            ))
+            .is_err());
+            assert!(serde_json::from_str::<Connect4>(&game(
+                r#""OutOfBounds","Empty","Empty","Empty""#
+            ))
+            .is_err());
+        }
+    }
+
+    mod pop_out {
+        use super::super::*;
+        use super::drops;
 
-            game.board.cells[3][3] = Cell::Token(Token::Black);
+        #[test]
+        fn moves() {
+            let (game, token) = replay(PopOut::new(4, 3, 3), &drops(&[0, 1])).unwrap();
+            assert_eq!(
+                game.moves(token).collect::<Vec<_>>(),
+                vec![
+                    Move::Drop(0),
+                    Move::Pop(0),
+                    Move::Drop(1),
+                    Move::Drop(2),
+                    Move::Drop(3)
+                ]
+            );
+            assert_eq!(game.play(token, Move::Pop(1)).err(), Some(Error::CannotPop));
             assert_eq!(
-                Connect4::victory(Token::White, &Position { x: 5, y: 5 }, &game.board),
-                false
+                game.play(token, Move::Pop(4)).err(),
+                Some(Error::OutOfBounds)
             );
             assert_eq!(
-                Connect4::victory(Token::Black, &Position { x: 5, y: 5 }, &game.board),
-                true
+                Connect4::new(4, 3, 3).play(token, Move::Pop(0)).err(),
+                Some(Error::CannotPop)
             );
+
+            let game = game.play(token, Move::Pop(0)).unwrap();
+            assert_eq!(game.cell(0, 2), None);
+            assert_eq!(game.move_count(), 1);
+            assert_eq!(game.side_to_move(), Token::Black);
         }
 
         #[test]
-        fn fall_position() {
-            let mut game = Connect4::new();
-            game.board.cells[3][2] = Cell::Token(Token::Black);
-            game.board.cells[6][4] = Cell::Token(Token::White);
+        fn wins() {
+            // Popping lines up the tokens of both players, and the one who popped wins
+            let (game, token) =
+                replay(PopOut::new(4, 3, 3), &drops(&[0, 0, 0, 1, 1, 2, 2, 2])).unwrap();
+            let popped = game.play(token, Move::Pop(0)).unwrap();
+            assert_eq!(popped.status(), Status::Victory);
+            assert_eq!(popped.cell(0, 2), Some(Token::Black));
 
-            assert_eq!(game.fall_position(0).unwrap(), Position { x: 0, y: 6 });
-            assert_eq!(game.fall_position(2).unwrap(), Position { x: 2, y: 2 });
-            assert_eq!(game.fall_position(4).unwrap(), Position { x: 4, y: 5 });
+            // Lining up the tokens of the other player only loses
+            let (game, token) =
+                replay(PopOut::new(4, 3, 3), &drops(&[0, 0, 0, 1, 1, 2, 3, 2])).unwrap();
+            assert_eq!(
+                game.play(token, Move::Pop(0)).unwrap().status(),
+                Status::Defeat
+            );
+        }
+
+        #[test]
+        fn ties() {
+            // The empty board comes up for the third time
+            let cycle = [Move::Drop(0), Move::Drop(1), Move::Pop(0), Move::Pop(1)];
+            let moves = [cycle, cycle].concat();
+            let (game, _) = replay(PopOut::new(4, 3, 3), &moves[..7]).unwrap();
+            assert_eq!(game.status(), Status::Ongoing);
+            let (game, _) = replay(PopOut::new(4, 3, 3), &moves).unwrap();
+            assert_eq!(game.status(), Status::Tie);
+
+            // A full board is a draw only when the player to move has nothing to pop
+            let (game, _) = replay(PopOut::new(2, 1, 2), &drops(&[0, 1])).unwrap();
+            assert_eq!(game.status(), Status::Ongoing);
+            assert_eq!(game.side_to_move(), Token::White);
+            assert_eq!(game.legal_moves().collect::<Vec<_>>(), vec![Move::Pop(0)]);
+            let (game, _) = replay(
+                PopOut::new(2, 1, 2),
+                &[Move::Drop(0), Move::Drop(1), Move::Pop(0)],
+            )
+            .unwrap();
+            assert_eq!(game.status(), Status::Ongoing);
+        }
+
+        #[test]
+        fn notation() {
+            assert_eq!(Move::Pop(3).to_string(), "p4");
+            assert_eq!("12".parse(), Ok(Move::Drop(11)));
+            assert_eq!("p1".parse(), Ok(Move::Pop(0)));
+            assert_eq!("0".parse::<Move>(), Err(()));
+            assert_eq!("x1".parse::<Move>(), Err(()));
+            assert_eq!("popout".parse(), Ok(Variant::PopOut));
+            assert_eq!(variant(Variant::PopOut, 4, 3, 3).variant(), Variant::PopOut);
+        }
+    }
+
+    mod pop10 {
+        use super::super::*;
+        use super::drops;
+        use core::convert::TryFrom;
+
+        // A board filled row by row from the top, W for white and B for black
+        fn filled(rows: &[&str], win: u8) -> Pop10 {
+            let width = u8::try_from(rows[0].len()).unwrap();
+            let mut game = Pop10::new(width, u8::try_from(rows.len()).unwrap(), win);
+            for (y, row) in rows.iter().enumerate() {
+                for (x, cell) in row.chars().enumerate() {
+                    let token = if cell == 'W' {
+                        Token::White
+                    } else {
+                        Token::Black
+                    };
+                    game.game.board[y][x] = Cell::Token(token);
+                }
+            }
+            game
+        }
+
+        #[test]
+        fn setup() {
+            let game = Pop10::new(3, 2, 2).place(Token::White, 0).unwrap();
+            assert_eq!(game.place(Token::Black, 0).err(), Some(Error::Forbidden));
+            assert_eq!(
+                game.play(Token::White, Move::Pop(0)).err(),
+                Some(Error::CannotPop)
+            );
+
+            // Lines count for nothing until the board is full
+            let (game, token) = replay(Pop10::new(3, 2, 2), &drops(&[0, 1, 2, 0, 1, 2])).unwrap();
+            assert_eq!(game.status(), Status::Ongoing);
+            assert_eq!(token, Token::White);
+            assert_eq!(game.side_to_move(), Token::White);
+            assert_eq!(
+                game.moves(token).collect::<Vec<_>>(),
+                vec![Move::Pop(0), Move::Pop(2)]
+            );
+        }
+
+        #[test]
+        fn collect() {
+            let game = filled(&["BWBB", "WBBW"], 2);
+            assert_eq!(
+                game.play(Token::White, Move::Pop(3)).err(),
+                Some(Error::Forbidden)
+            );
+            let game = game.play(Token::White, Move::Pop(0)).unwrap();
+            assert_eq!(game.collected(Token::White), Some(1));
+            assert_eq!(game.cell(0, 0), None);
+            assert_eq!(game.cell(0, 1), Some(Token::Black));
+            assert_eq!(game.side_to_move(), Token::Black);
+            assert_eq!(game.status(), Status::Ongoing);
+
+            // Without a line, the token goes back on top
+            let game = filled(&["BBBB", "WBWB"], 2)
+                .play(Token::White, Move::Pop(0))
+                .unwrap();
+            assert_eq!(game.collected(Token::White), Some(0));
+            assert_eq!(game.cell(0, 0), Some(Token::White));
+            assert_eq!(game.cell(0, 1), Some(Token::Black));
+
+            // On top of the tokens left in a column that lost some
+            let mut game = filled(&["BWWB", "BBBB", "WBWB"], 2);
+            game.game.board[0][0] = Cell::Empty;
+            game.collected = [1, 0];
+            let game = game.play(Token::White, Move::Pop(0)).unwrap();
+            assert_eq!(game.cell(0, 0), None);
+            assert_eq!(game.cell(0, 1), Some(Token::White));
+            assert_eq!(game.cell(0, 2), Some(Token::Black));
+        }
+
+        #[test]
+        fn wins() {
+            let mut game = filled(&["BWBB", "WBBW"], 2);
+            game.collected = [COLLECT - 1, 0];
+            let game = game.play(Token::White, Move::Pop(0)).unwrap();
+            assert_eq!(game.status(), Status::Victory);
+
+            // Nothing left for black to pop
+            let game = filled(&["WB", "WW"], 2)
+                .play(Token::White, Move::Pop(0))
+                .unwrap();
+            assert_eq!(game.status(), Status::Victory);
+        }
+
+        #[test]
+        fn repetition() {
+            let mut game = filled(&["BWB", "WBW"], 3);
+            let mut token = Token::White;
+            for _ in 0..4 {
+                game = game.play(token, Move::Pop(0)).unwrap();
+                assert_eq!(game.status(), Status::Ongoing);
+                token = !token;
+            }
+            game = game.play(token, Move::Pop(0)).unwrap();
+            assert_eq!(game.status(), Status::Tie);
+        }
+
+        #[test]
+        fn notation() {
+            assert_eq!("pop10".parse(), Ok(Variant::Pop10));
+            let game = variant(Variant::Pop10, 4, 3, 3);
+            assert_eq!(game.variant(), Variant::Pop10);
+            assert_eq!(game.collected(Token::Black), Some(0));
+            assert!(game
+                .to_string()
+                .ends_with("Collected: \u{2593}\u{2593} 0  \u{2591}\u{2591} 0\n"));
+        }
+    }
+
+    mod cylinder {
+        use super::super::*;
+
+        #[test]
+        fn wraps() {
+            let mut game = Connect4::cylinder(5, 4, 4);
+            for x in [3, 4, 0] {
+                game = game.place(Token::White, x).unwrap();
+                assert_eq!(game.status(), Status::Ongoing);
+            }
+            assert_eq!(game.plan(Token::White, 1), Ok(Status::Victory));
+            assert_eq!(game.plan(Token::White, 2), Ok(Status::Victory));
+
+            let mut game = Connect4::new(5, 4, 4);
+            for x in [3, 4, 0] {
+                game = game.place(Token::White, x).unwrap();
+            }
+            assert_eq!(game.plan(Token::White, 1), Ok(Status::Ongoing));
+
+            // Diagonally, up from the last column to the first
+            let mut game = Connect4::cylinder(5, 4, 3);
+            game.board[3][4] = Cell::Token(Token::White);
+            game.board[3][0] = Cell::Token(Token::Black);
+            game.board[2][0] = Cell::Token(Token::White);
+            game.board[3][1] = Cell::Token(Token::Black);
+            game.board[2][1] = Cell::Token(Token::Black);
+            assert_eq!(game.plan(Token::White, 1), Ok(Status::Victory));
+            assert_eq!(AnyGame::from(game).variant(), Variant::Cylinder);
+        }
+
+        #[test]
+        fn narrow() {
+            // Three tokens round a row of three are not four in a row
+            let mut game = Connect4::cylinder(3, 4, 4);
+            for x in 0..3 {
+                game = game.place(Token::White, x).unwrap();
+            }
+            assert_eq!(game.status(), Status::Ongoing);
+
+            let mut game = Connect4::cylinder(3, 4, 3);
+            for x in 0..3 {
+                game = game.place(Token::White, x).unwrap();
+            }
+            assert_eq!(game.status(), Status::Victory);
+            assert_eq!("cylinder".parse(), Ok(Variant::Cylinder));
+        }
+    }
+
+    mod free {
+        use super::super::*;
+
+        #[test]
+        fn places() {
+            let game = Free::new(4, 3, 3)
+                .play(Token::White, Move::Place(1, 0))
+                .unwrap();
+            assert_eq!(game.cell(1, 0), Some(Token::White));
+            assert_eq!(game.cell(1, 2), None);
+            assert_eq!(game.column_height(1), 1);
+            assert_eq!(game.move_count(), 1);
+            assert_eq!(game.side_to_move(), Token::Black);
+            assert_eq!(game.moves(Token::Black).count(), 11);
+            assert_eq!(game.legal_moves().count(), 11);
+            assert!(game
+                .legal_moves()
+                .all(|action| matches!(action, Move::Place(..))));
+
+            let play = |action| game.play(Token::Black, action).err();
+            assert_eq!(play(Move::Place(1, 0)), Some(Error::Occupied));
+            assert_eq!(play(Move::Place(4, 0)), Some(Error::OutOfBounds));
+            assert_eq!(play(Move::Drop(0)), Some(Error::Forbidden));
+            assert_eq!(play(Move::Pop(1)), Some(Error::CannotPop));
+            assert_eq!(
+                Connect4::new(4, 3, 3)
+                    .play(Token::White, Move::Place(0, 2))
+                    .err(),
+                Some(Error::Forbidden)
+            );
+        }
+
+        #[test]
+        fn status() {
+            let moves = [(0, 0), (1, 0), (1, 1), (2, 1)]
+                .iter()
+                .map(|(x, y)| Move::Place(*x, *y))
+                .collect::<Vec<_>>();
+            let (game, token) = replay(Free::new(3, 3, 3), &moves).unwrap();
+            assert_eq!(game.plan(token, 2), Err(Error::Forbidden));
+            let won = game.play(token, Move::Place(2, 2)).unwrap();
+            assert_eq!(won.status(), Status::Victory);
+
+            let mut game = Free::new(2, 1, 2);
+            game = game.play(Token::White, Move::Place(0, 0)).unwrap();
+            game = game.play(Token::Black, Move::Place(1, 0)).unwrap();
+            assert_eq!(game.status(), Status::Tie);
+        }
+
+        #[test]
+        fn notation() {
+            assert_eq!(Move::Place(3, 11).to_string(), "4:12");
+            assert_eq!("4:2".parse(), Ok(Move::Place(3, 1)));
+            assert_eq!("4,2".parse(), Ok(Move::Place(3, 1)));
+            assert_eq!("4:0".parse::<Move>(), Err(()));
+            assert_eq!("p4:2".parse::<Move>(), Err(()));
+            assert_eq!("free".parse(), Ok(Variant::Free));
+
+            let game = variant(Variant::Free, 3, 2, 2);
+            assert!(!game.gravity());
+            assert_eq!(game.to_string().lines().next(), Some("|  |  |  | 1"));
         }
     }
 
@@ -398,7 +1923,7 @@ mod tests {
 
         #[test]
         fn out_of_bounds() {
-            let board = Board::new();
+            let board = Board::new(7, 7);
             assert_eq!(board.cell(&Position { x: 8, y: 1 }), Cell::OutOfBounds);
             assert_eq!(board.cell(&Position { x: 1, y: 8 }), Cell::OutOfBounds);
             assert_eq!(board.cell(&Position { x: 8, y: 8 }), Cell::OutOfBounds);
@@ -407,8 +1932,8 @@ mod tests {
 
         #[test]
         fn is_token() {
-            let mut board = Board::new();
-            board.cells[3][2] = Cell::Token(Token::Black);
+            let mut board = Board::new(7, 7);
+            board[3][2] = Cell::Token(Token::Black);
 
             assert!(!board.is_token(Token::Black, &Position { x: 8, y: 8 }));
             assert!(!board.is_token(Token::Black, &Position { x: 0, y: 0 }));
@@ -0,0 +1,628 @@
+// JSON API over HTTP, answering one request per connection. Columns count from 1, and every
+// request may give the width, height and win of the board, the AI as given to play and the
+// budget it may think:
+//
+//   POST /move              {"moves": [4, 4], "ai": "aclub"}   The move of the AI, with its scores
+//   POST /solve             {"moves": [4, 4]}                  The best move, with its scores
+//   POST /validate          {"moves": [4, 4]}                  The position, or why it is not one
+//   POST /games             {"colour": "black"}                A game against the AI, which moves
+//                                                              first if the human is black
+//   GET  /games/{id}                                           The game
+//   POST /games/{id}/move   {"column": 3}                      The game after the move and the
+//                                                              reply of the AI
+//
+// Games are forgotten a minute after they are last asked for once over, or half an hour after
+// while still going on.
+#[derive(Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Request {
+    moves: Vec<u8>,
+    column: Option<u8>,
+    colour: Option<String>,
+    width: Option<u8>,
+    height: Option<u8>,
+    win: Option<u8>,
+    ai: Option<String>,
+    budget: Option<String>,
+}
+
+pub struct Server {
+    settings: super::config::Settings,
+    ai: super::config::Spec,
+    games: std::sync::Mutex<Games>,
+}
+
+#[derive(Default)]
+struct Games {
+    next: u64,
+    sessions: std::collections::HashMap<u64, Session>,
+}
+
+// A game between a human, who sends moves, and the AI
+#[derive(Clone)]
+struct Session {
+    settings: super::config::Settings,
+    ai: super::config::Spec,
+    human: super::game::Token,
+    moves: Vec<super::game::Move>,
+    // When it was last asked for
+    used: std::time::Instant,
+}
+
+type Answer = (u16, serde_json::Value);
+
+impl Games {
+    fn sweep(&mut self, now: std::time::Instant) {
+        self.sessions.retain(|_, session| {
+            let idle = now.saturating_duration_since(session.used);
+            idle < Server::ABANDONED && (idle < Server::FINISHED || !session.over())
+        });
+    }
+}
+
+impl Server {
+    // How long games are kept after they are last asked for, while going on and once over
+    const ABANDONED: std::time::Duration = std::time::Duration::from_secs(30 * 60);
+    const FINISHED: std::time::Duration = std::time::Duration::from_secs(60);
+
+    // The AI is the one for requests that do not name one
+    pub fn new(settings: super::config::Settings, ai: super::config::Spec) -> Self {
+        Self {
+            settings,
+            ai,
+            games: std::sync::Mutex::new(Games::default()),
+        }
+    }
+
+    // Answers every connection on its own thread
+    pub fn serve(self, listener: &std::net::TcpListener) {
+        let server = std::sync::Arc::new(self);
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("Could not accept a connection: {e}");
+                    continue;
+                }
+            };
+
+            let server = server.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = server.handle(stream) {
+                    eprintln!("Connection lost: {e}");
+                }
+            });
+        }
+    }
+
+    fn handle(&self, stream: std::net::TcpStream) -> std::io::Result<()> {
+        use std::io::{BufRead, Read, Write};
+        const MAX_BODY: usize = 64 * 1024;
+
+        let mut reader = std::io::BufReader::new(stream.try_clone()?);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let mut parts = line.split_whitespace();
+        let (method, path) = (
+            parts.next().unwrap_or_default().to_string(),
+            parts.next().unwrap_or_default().to_string(),
+        );
+
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+
+        let (code, body) = if length > MAX_BODY {
+            error(413, "the body is too large")
+        } else {
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body)?;
+            match String::from_utf8(body) {
+                Ok(body) => self.answer(&method, &path, &body),
+                Err(_) => error(400, "the body is not UTF-8"),
+            }
+        };
+
+        let body = if code == 204 {
+            String::new()
+        } else {
+            body.to_string()
+        };
+        let mut stream = stream;
+        write!(
+            stream,
+            "HTTP/1.1 {code} {}\r\n\
+             Content-Type: application/json\r\n\
+             Content-Length: {}\r\n\
+             Access-Control-Allow-Origin: *\r\n\
+             Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+             Access-Control-Allow-Headers: Content-Type\r\n\
+             Connection: close\r\n\r\n{body}",
+            reason(code),
+            body.len()
+        )?;
+        stream.flush()
+    }
+
+    fn answer(&self, method: &str, path: &str, body: &str) -> Answer {
+        if method == "OPTIONS" {
+            return (204, serde_json::Value::Null);
+        }
+
+        let request = if body.trim().is_empty() {
+            Request::default()
+        } else {
+            match serde_json::from_str::<Request>(body) {
+                Ok(request) => request,
+                Err(e) => return error(400, &format!("the body is not a request: {e}")),
+            }
+        };
+
+        let segments = path
+            .trim_matches('/')
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+        let result = match (method, segments.as_slice()) {
+            ("POST", ["move"]) => self.play(&request),
+            ("POST", ["solve"]) => self.solve(&request),
+            ("POST", ["validate"]) => self.validate(&request),
+            ("POST", ["games"]) => self.create(&request),
+            ("GET", ["games", id]) => self.game(id),
+            ("POST", ["games", id, "move"]) => self.move_in(id, &request),
+            (
+                _,
+                ["move" | "solve" | "validate" | "games"] | ["games", _] | ["games", _, "move"],
+            ) => Err(error(405, &format!("{method} is not allowed on {path}"))),
+            _ => Err(error(404, &format!("{path} is not known"))),
+        };
+        result.unwrap_or_else(|answer| answer)
+    }
+
+    fn play(&self, request: &Request) -> Result<Answer, Answer> {
+        let settings = self.settings(request)?;
+        let ai = self.ai(request, &settings)?;
+        let moves = columns(&request.moves)?;
+        let (game, token) = super::engine::ongoing(&settings, &moves).map_err(invalid)?;
+        let column = ai.best_move(&game, token);
+        Ok((200, best(column, &ai)))
+    }
+
+    fn solve(&self, request: &Request) -> Result<Answer, Answer> {
+        let settings = self.settings(request)?;
+        let moves = columns(&request.moves)?;
+        let (game, token) = super::engine::ongoing(&settings, &moves).map_err(invalid)?;
+        let ai = super::engine::analyst(&settings);
+        let column = ai.best_move(&game, token);
+        Ok((200, best(column, &ai)))
+    }
+
+    fn validate(&self, request: &Request) -> Result<Answer, Answer> {
+        let settings = self.settings(request)?;
+        let moves = columns(&request.moves)?;
+        Ok(match super::engine::position(&settings, &moves) {
+            Ok((game, token)) => {
+                let mut state = state(&game, token, &moves);
+                state["valid"] = serde_json::Value::Bool(true);
+                (200, state)
+            }
+            Err(message) => (200, serde_json::json!({ "valid": false, "error": message })),
+        })
+    }
+
+    fn create(&self, request: &Request) -> Result<Answer, Answer> {
+        let settings = self.settings(request)?;
+        let ai = self.spec(request)?;
+        self.ai(request, &settings)?;
+        let human = match request.colour.as_deref() {
+            None | Some("white") => super::game::Token::White,
+            Some("black") => super::game::Token::Black,
+            Some(colour) => return Err(error(400, &format!("{colour} is not white or black"))),
+        };
+
+        let mut session = Session {
+            settings,
+            ai,
+            human,
+            moves: columns(&request.moves)?,
+            used: std::time::Instant::now(),
+        };
+        super::engine::position(&session.settings, &session.moves).map_err(invalid)?;
+        if let Some(reply) = session.reply()? {
+            session.moves.push(reply);
+        }
+
+        let mut games = self.lock();
+        games.sweep(std::time::Instant::now());
+        games.next += 1;
+        let id = games.next;
+        let answer = session.state(id);
+        games.sessions.insert(id, session);
+        Ok((201, answer))
+    }
+
+    fn game(&self, id: &str) -> Result<Answer, Answer> {
+        let id = parse_id(id)?;
+        let mut games = self.lock();
+        let session = games.sessions.get_mut(&id).ok_or_else(|| unknown(id))?;
+        session.used = std::time::Instant::now();
+        Ok((200, session.state(id)))
+    }
+
+    // The move is kept while the AI replies without locking the games, so that the game tells it
+    // is the turn of the AI and other games are answered meanwhile
+    fn move_in(&self, id: &str, request: &Request) -> Result<Answer, Answer> {
+        let id = parse_id(id)?;
+        let column = request
+            .column
+            .ok_or_else(|| error(400, "the column to play is missing"))?;
+        let column = columns(&[column])?[0];
+
+        let session = {
+            let mut games = self.lock();
+            let session = games.sessions.get_mut(&id).ok_or_else(|| unknown(id))?;
+            session.used = std::time::Instant::now();
+            let (_, token) =
+                super::engine::ongoing(&session.settings, &session.moves).map_err(invalid)?;
+            if token != session.human {
+                return Err(error(409, "it is the turn of the AI"));
+            }
+
+            session.moves.push(column);
+            if let Err(message) = super::engine::position(&session.settings, &session.moves) {
+                session.moves.pop();
+                return Err(invalid(message));
+            }
+            session.clone()
+        };
+        let reply = session.reply()?;
+
+        let mut games = self.lock();
+        let session = games.sessions.get_mut(&id).ok_or_else(|| unknown(id))?;
+        if let Some(reply) = reply {
+            session.moves.push(reply);
+        }
+        Ok((200, session.state(id)))
+    }
+
+    fn settings(&self, request: &Request) -> Result<super::config::Settings, Answer> {
+        let mut settings = self.settings.clone();
+        settings.width = request.width.unwrap_or(settings.width);
+        settings.height = request.height.unwrap_or(settings.height);
+        settings.win = request.win.unwrap_or(settings.win);
+        if let Some(budget) = &request.budget {
+            settings.budget = Some(
+                super::config::parse_duration(budget)
+                    .filter(|budget| !budget.is_zero())
+                    .ok_or_else(|| error(400, &format!("budget is not a duration: {budget}")))?,
+            );
+        }
+        settings.check().map_err(invalid)?;
+        Ok(settings)
+    }
+
+    fn spec(&self, request: &Request) -> Result<super::config::Spec, Answer> {
+        match &request.ai {
+            Some(spec) => spec
+                .parse()
+                .map_err(|()| error(400, &format!("ai is not a player: {spec}"))),
+            None => Ok(self.ai),
+        }
+    }
+
+    fn ai(
+        &self,
+        request: &Request,
+        settings: &super::config::Settings,
+    ) -> Result<super::ai::Ai, Answer> {
+        settings
+            .ai(self.spec(request)?)
+            .ok_or_else(|| error(400, "ai must be an AI player"))
+    }
+
+    // A panic answering another request leaves the games as they were
+    fn lock(&self) -> std::sync::MutexGuard<'_, Games> {
+        self.games
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl Session {
+    // Whether the game is finished
+    fn over(&self) -> bool {
+        super::engine::ongoing(&self.settings, &self.moves).is_err()
+    }
+
+    // The move of the AI if it is its turn
+    fn reply(&self) -> Result<Option<super::game::Move>, Answer> {
+        match super::engine::ongoing(&self.settings, &self.moves) {
+            Ok((game, token)) if token != self.human => {
+                let ai = self
+                    .settings
+                    .ai(self.ai)
+                    .ok_or_else(|| error(400, "ai must be an AI player"))?;
+                Ok(Some(ai.best_move(&game, token)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn state(&self, id: u64) -> serde_json::Value {
+        let mut state = match super::engine::position(&self.settings, &self.moves) {
+            Ok((game, token)) => state(&game, token, &self.moves),
+            Err(message) => serde_json::json!({ "error": message }),
+        };
+        state["id"] = serde_json::json!(id);
+        state["human"] = serde_json::json!(colour(self.human));
+        state["ai"] = serde_json::json!(self.ai.to_string());
+        state
+    }
+}
+
+// The board as rows from the top, with X for white, O for black and . for empty cells. Games
+// here are classic ones, so that their moves are columns
+pub fn state<Game: super::game::Game>(
+    game: &Game,
+    token: super::game::Token,
+    moves: &[super::game::Move],
+) -> serde_json::Value {
+    let board = (0..game.height())
+        .map(|y| {
+            (0..game.size())
+                .map(|x| match game.cell(x, y) {
+                    Some(super::game::Token::White) => 'X',
+                    Some(super::game::Token::Black) => 'O',
+                    None => '.',
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut state = serde_json::json!({
+        "moves": moves.iter().map(|action| action.column() + 1).collect::<Vec<_>>(),
+        "width": game.size(),
+        "height": game.height(),
+        "win": game.win(),
+        "board": board,
+    });
+    match game.status() {
+        super::game::Status::Victory => {
+            state["status"] = serde_json::json!("victory");
+            state["winner"] = serde_json::json!(colour(!token));
+        }
+        super::game::Status::Defeat => {
+            state["status"] = serde_json::json!("victory");
+            state["winner"] = serde_json::json!(colour(token));
+        }
+        super::game::Status::Tie => state["status"] = serde_json::json!("tie"),
+        super::game::Status::Ongoing | super::game::Status::Timeout => {
+            state["status"] = serde_json::json!("ongoing");
+            state["next"] = serde_json::json!(colour(token));
+        }
+    }
+    state
+}
+
+fn best(action: super::game::Move, ai: &super::ai::Ai) -> serde_json::Value {
+    let scores = ai
+        .scores()
+        .into_iter()
+        .map(|(action, score)| serde_json::json!({ "column": action.column() + 1, "score": score }))
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "column": action.column() + 1,
+        "scores": scores,
+        "analysis": ai.analysis(),
+    })
+}
+
+fn colour(token: super::game::Token) -> &'static str {
+    match token {
+        super::game::Token::White => "white",
+        super::game::Token::Black => "black",
+    }
+}
+
+fn columns(moves: &[u8]) -> Result<Vec<super::game::Move>, Answer> {
+    moves
+        .iter()
+        .map(|column| column.checked_sub(1).map(super::game::Move::Drop))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| error(400, "columns count from 1"))
+}
+
+fn parse_id(id: &str) -> Result<u64, Answer> {
+    id.parse()
+        .map_err(|_| error(404, &format!("{id} is not a game")))
+}
+
+fn unknown(id: u64) -> Answer {
+    error(404, &format!("game {id} does not exist"))
+}
+
+fn invalid(message: String) -> Answer {
+    (
+        422,
+        serde_json::json!({ "error": serde_json::Value::from(message) }),
+    )
+}
+
+fn error(code: u16, message: &str) -> Answer {
+    (code, serde_json::json!({ "error": message }))
+}
+
+fn reason(code: u16) -> &'static str {
+    match code {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod http {
+        use super::super::*;
+
+        fn server() -> Server {
+            let settings = super::super::super::config::Settings {
+                depth: Some(4),
+                width: 7,
+                height: 6,
+                ..Default::default()
+            };
+            Server::new(settings, super::super::super::config::Spec::Ai(None))
+        }
+
+        #[test]
+        fn positions() {
+            let server = server();
+            let (code, answer) = server.answer("POST", "/validate", r#"{"moves": [1, 2, 1]}"#);
+            assert_eq!(code, 200);
+            assert_eq!(answer["valid"], true);
+            assert_eq!(answer["next"], "black");
+            assert_eq!(answer["board"][5], "XO.....");
+
+            let (_, answer) = server.answer("POST", "/validate", r#"{"moves": [8]}"#);
+            assert_eq!(answer["valid"], false);
+            assert_eq!(answer["error"], "move 1 in column 8: out of bounds");
+
+            // White is about to complete the first column
+            let (code, answer) = server.answer("POST", "/solve", r#"{"moves": [1, 2, 1, 2, 1]}"#);
+            assert_eq!(code, 200);
+            assert_eq!(answer["column"], 1);
+            assert_eq!(answer["scores"].as_array().unwrap().len(), 7);
+
+            let (code, answer) = server.answer("POST", "/move", r#"{"moves": [], "ai": "a2"}"#);
+            assert_eq!(code, 200);
+            assert!(answer["column"].as_u64().is_some());
+        }
+
+        #[test]
+        fn errors() {
+            let server = server();
+            assert_eq!(server.answer("POST", "/move", "{").0, 400);
+            assert_eq!(server.answer("POST", "/move", r#"{"colors": 1}"#).0, 400);
+            assert_eq!(server.answer("POST", "/move", r#"{"ai": "h"}"#).0, 400);
+            assert_eq!(server.answer("POST", "/move", r#"{"moves": [0]}"#).0, 400);
+            assert_eq!(server.answer("POST", "/move", r#"{"width": 17}"#).0, 422);
+            assert_eq!(
+                server
+                    .answer("POST", "/solve", r#"{"moves": [1,2,1,2,1,2,1]}"#)
+                    .0,
+                422
+            );
+            assert_eq!(server.answer("GET", "/move", "").0, 405);
+            assert_eq!(server.answer("GET", "/games/1", "").0, 404);
+            assert_eq!(server.answer("GET", "/nothing", "").0, 404);
+            assert_eq!(server.answer("OPTIONS", "/move", "").0, 204);
+        }
+
+        #[test]
+        fn games() {
+            let server = server();
+            let (code, answer) = server.answer("POST", "/games", r#"{"colour": "black"}"#);
+            assert_eq!(code, 201);
+            assert_eq!(answer["id"], 1);
+            assert_eq!(answer["moves"].as_array().unwrap().len(), 1);
+            assert_eq!(answer["next"], "black");
+
+            let (code, answer) = server.answer("POST", "/games/1/move", r#"{"column": 1}"#);
+            assert_eq!(code, 200);
+            assert_eq!(answer["moves"].as_array().unwrap().len(), 3);
+            assert_eq!(server.answer("GET", "/games/1", "").1, answer);
+
+            assert_eq!(server.answer("POST", "/games/1/move", "{}").0, 400);
+            assert_eq!(
+                server.answer("POST", "/games/2/move", r#"{"column": 1}"#).0,
+                404
+            );
+            assert_eq!(
+                server.answer("POST", "/games", r#"{"colour": "red"}"#).0,
+                400
+            );
+
+            // Forgotten soon when over, later when not
+            let over = r#"{"moves": [1, 2, 1, 2, 1, 2, 1]}"#;
+            assert_eq!(server.answer("POST", "/games", over).0, 201);
+            let later = |duration| std::time::Instant::now() + duration;
+            server.lock().sweep(later(Server::FINISHED));
+            assert_eq!(server.answer("GET", "/games/1", "").0, 200);
+            assert_eq!(server.answer("GET", "/games/2", "").0, 404);
+            server.lock().sweep(later(Server::ABANDONED));
+            assert_eq!(server.answer("GET", "/games/1", "").0, 404);
+        }
+
+        #[test]
+        fn answers_while_thinking() {
+            let settings = super::super::super::config::Settings {
+                budget: Some(std::time::Duration::from_millis(500)),
+                ..Default::default()
+            };
+            let server = Server::new(settings, super::super::super::config::Spec::Ai(None));
+            assert_eq!(server.answer("POST", "/games", "{}").0, 201);
+
+            std::thread::scope(|scope| {
+                let thinking =
+                    scope.spawn(|| server.answer("POST", "/games/1/move", r#"{"column": 4}"#));
+                std::thread::sleep(std::time::Duration::from_millis(100));
+
+                let start = std::time::Instant::now();
+                let (code, answer) = server.answer("GET", "/games/1", "");
+                assert!(start.elapsed() < std::time::Duration::from_millis(200));
+                assert_eq!(code, 200);
+                assert_eq!(answer["next"], "black");
+                assert_eq!(
+                    server.answer("POST", "/games/1/move", r#"{"column": 4}"#).0,
+                    409
+                );
+                assert_eq!(server.answer("POST", "/games", "{}").0, 201);
+
+                let (code, answer) = thinking.join().unwrap();
+                assert_eq!(code, 200);
+                assert_eq!(answer["moves"].as_array().unwrap().len(), 2);
+            });
+        }
+
+        #[test]
+        fn connection() {
+            use std::io::{Read, Write};
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let address = listener.local_addr().unwrap();
+            std::thread::spawn(move || server().serve(&listener));
+
+            let body = r#"{"moves": [4]}"#;
+            let mut stream = std::net::TcpStream::connect(address).unwrap();
+            write!(
+                stream,
+                "POST /validate HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+            let (_, json) = response.split_once("\r\n\r\n").unwrap();
+            let answer = serde_json::from_str::<serde_json::Value>(json).unwrap();
+            assert_eq!(answer["moves"][0], 4);
+        }
+    }
+}
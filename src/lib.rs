@@ -0,0 +1,25 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![deny(warnings)]
+#![deny(clippy::pedantic)]
+#![warn(rust_2018_idioms)]
+// The types speak for themselves, as they do in the game
+#![allow(
+    clippy::must_use_candidate,
+    clippy::missing_errors_doc,
+    clippy::missing_panics_doc
+)]
+
+// The rules and the AI, for the game and for other programs to embed. Without the std feature,
+// only the rules are there, for boards with nothing but an allocator
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod ai;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod game;
+#[cfg(feature = "python")]
+pub mod python;
+
+#[cfg(all(feature = "std", target_arch = "wasm32"))]
+pub mod wasm;
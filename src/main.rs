@@ -2,175 +2,381 @@
 #![deny(clippy::pedantic)]
 #![warn(rust_2018_idioms)]
 
-mod game;
-mod player;
-
-enum Result {
-    Players(player::Player, player::Player),
-    Help,
-    Error,
-}
-
-fn usage() {
-    println!("Usage: connect4 [-h] [-v] [PLAYER [PLAYER]]");
-    println!("    PLAYER:");
-    println!("        h              Human player");
-    println!("        a[level]       AI player, where level=difficulty");
-    println!("    -h                 Show this help message");
-    println!("    -v                 If an AI is present, make it verbose");
-    println!();
-    println!("Example:");
-    println!("    connect4           White: Human, Black: AI[level=8]");
-    println!("    connect4 a6 h      White: AI[level=6], Black: Human");
-    println!("    connect4 h         White: Human, Black: Human");
-    println!("    connect4 a a9      White: AI[level=8], Black: AI[level=9]");
-}
+use connect4::{ai, game};
 
-// TODO: Make the stateful canvas less messy
-//       If verbose, printing goes whack
-fn print<Game: game::Game>(game: &Game, error: &mut Option<String>, clear_size: usize) -> usize {
-    for _ in 0..clear_size {
-        print!("\x1b[0K");
-        print!("\x1b[1A");
-    }
-    print!("\x1b[K");
-    println!("{}", &game);
-
-    if let Some(message) = error {
-        println!("Error: {}", message);
-        *error = None;
-        12
-    } else {
-        11
-    }
-}
+mod cli;
+mod clock;
+mod config;
+mod engine;
+mod http;
+mod online;
+mod player;
+mod record;
+mod replay;
+mod tournament;
+mod ui;
 
-fn prepare_canvas() -> usize {
-    for _ in 0..11 {
-        println!();
-    }
-    11
+// How a game ended: the final position, the winner if there is one, what happened and the moves
+// that led there
+struct Ending<Game> {
+    game: Game,
+    winner: Option<game::Token>,
+    message: String,
+    moves: Vec<record::Move>,
 }
 
-fn start(white: &player::Player, black: &player::Player) {
+// Nothing when a player quits
+fn start(
+    white: &player::Player,
+    black: &player::Player,
+    settings: &config::Settings,
+    ui: &mut ui::Ui,
+) -> Option<Ending<impl game::Game>> {
     use game::Game;
-    let mut game = game::new();
+    let mut game = game::variant(
+        settings.variant,
+        settings.width,
+        settings.height,
+        settings.win,
+    );
     let mut token = game::Token::White;
-    let mut error: Option<String> = None;
-    let mut clear_size = prepare_canvas();
+    let mut clock = settings.time.map(clock::Clock::new);
+    let mut moves = Vec::new();
 
     loop {
-        clear_size = print(&game, &mut error, clear_size);
+        if let Some(clock) = &mut clock {
+            clock.start(token);
+            ui.clock(clock);
+        }
+        ui.draw(&game);
 
+        let limit = clock.as_ref().map(|clock| clock.budget(token, &game));
         let play = match token {
-            game::Token::White => white.play(&game, token),
-            game::Token::Black => black.play(&game, token),
+            game::Token::White => white.play(&game, token, limit, ui),
+            game::Token::Black => black.play(&game, token, limit, ui),
         };
 
-        match play {
-            player::Result::Ok(input) => match game.place(token, input) {
-                Ok(new_state) => {
-                    game = new_state;
-                    match game.status() {
-                        game::Status::Victory => {
-                            print(&game, &mut None, clear_size);
-                            println!("Player {} won by playing {}", token, input + 1);
-                            break;
-                        }
-                        game::Status::Tie => {
-                            print(&game, &mut None, clear_size);
-                            println!("It's a draw...");
-                            break;
+        // Whatever was played came too late
+        let (status, played) = if clock.as_ref().is_some_and(clock::Clock::expired) {
+            (game::Status::Timeout, None)
+        } else {
+            match play {
+                player::Result::Ok(input) => match game.play(token, input) {
+                    Ok(new_state) => {
+                        if let Some(clock) = &mut clock {
+                            clock.stop();
+                            ui.clock(clock);
                         }
-                        _ => {}
+                        ui.record(&game, token, input);
+                        white.observe(token, input);
+                        black.observe(token, input);
+                        let player = match token {
+                            game::Token::White => white,
+                            game::Token::Black => black,
+                        };
+                        moves.push(record::Move {
+                            played: input,
+                            evaluation: player.evaluation(),
+                            comment: None,
+                        });
+                        game = new_state;
+                        (game.status(), Some(input))
                     }
-                    token = !token;
+                    Err(e) => {
+                        ui.error(e.to_string());
+                        continue;
+                    }
+                },
+                player::Result::Error(message) => {
+                    ui.error(message);
+                    continue;
                 }
-                Err(e) => {
-                    error = Some(e.to_string());
+                player::Result::Repeat => continue,
+                player::Result::Quit => return None,
+            }
+        };
+
+        let (winner, message) = match (status, played) {
+            (game::Status::Victory, Some(played)) => (
+                Some(token),
+                format!("Player {} won by playing {played}", ui.label(token)),
+            ),
+            (game::Status::Defeat, Some(played)) => (
+                Some(!token),
+                format!(
+                    "Player {} won as {} played {played}",
+                    ui.label(!token),
+                    ui.label(token)
+                ),
+            ),
+            (game::Status::Tie, _) => (None, String::from("It's a draw...")),
+            (game::Status::Timeout, _) => {
+                if let Some(last) = moves.last_mut() {
+                    let side = match token {
+                        game::Token::White => "White",
+                        game::Token::Black => "Black",
+                    };
+                    last.comment = Some(format!("{side} lost on time"));
                 }
-            },
-            player::Result::Error(message) => {
-                error = Some(message);
+                (
+                    Some(!token),
+                    format!("Player {} lost on time", ui.label(token)),
+                )
             }
-            player::Result::Repeat => {}
-            player::Result::Quit => {
-                break;
+            _ => {
+                token = !token;
+                continue;
             }
+        };
+        return Some(Ending {
+            game,
+            winner,
+            message,
+            moves,
+        });
+    }
+}
+
+// Ctrl-C makes a thinking AI play the best move found so far, otherwise it quits
+fn interrupt_on_ctrl_c(white: &player::Player, black: &player::Player) {
+    let searches = [white.search(), black.search()];
+    let handler = ctrlc::set_handler(move || {
+        let mut stopped = false;
+        for search in searches.iter().flatten() {
+            stopped |= search.stop();
         }
+        if !stopped {
+            std::process::exit(130);
+        }
+    });
+
+    if let Err(e) = handler {
+        eprintln!("Could not handle Ctrl-C: {e}");
     }
 }
 
-fn parse_args() -> Result {
-    let args = std::env::args().skip(1).collect::<Vec<_>>();
-    let mut verbose = false;
-    let mut white: Option<player::Player> = None;
-    let mut black: Option<player::Player> = None;
-
-    for arg in &args {
-        match arg.as_str() {
-            "-h" => {
-                if white.is_some() {
-                    return Result::Error;
-                }
-                return Result::Help;
-            }
-            "-v" => {
-                if white.is_some() {
-                    return Result::Error;
-                }
-                verbose = true;
-            }
-            "h" => {
-                if white.is_none() {
-                    white = Some(player::Player::Human)
-                } else if black.is_none() {
-                    black = Some(player::Player::Human)
-                }
-            }
-            _ => {
-                if let Some(c) = arg.chars().nth(0) {
-                    if c == 'a' {
-                        let level_string = arg.chars().skip(1).collect::<String>();
-                        let level = if level_string.is_empty() {
-                            Ok(8_u8)
-                        } else {
-                            level_string.parse::<u8>()
-                        };
-                        if let Ok(level) = level {
-                            if white.is_none() {
-                                white = Some(player::Player::Ai(player::Ai::new(level, verbose)));
-                                continue;
-                            } else if black.is_none() {
-                                black = Some(player::Player::Ai(player::Ai::new(level, verbose)));
-                                continue;
-                            }
-                        }
-                    }
-                }
-                return Result::Error;
+// A series of games, the players swapping colours after each one, ends when a player has won
+// more than half of them. Draws count as games played unless they are to be replayed
+fn play(settings: &config::Settings) {
+    use config::Spec;
+    let remotes = [settings.white, settings.black]
+        .iter()
+        .filter(|spec| **spec == Spec::Remote)
+        .count();
+    if remotes > 1 {
+        cli::fail("only one of the players can be remote");
+    }
+    if remotes > 0 && settings.games > 1 {
+        cli::fail("a series cannot be played with a remote player");
+    }
+    if remotes > 0 && settings.variant != game::Variant::Classic {
+        cli::fail("remote players only play the classic rules");
+    }
+
+    let players = settings
+        .player(settings.white, game::Token::White)
+        .and_then(|white| Ok([white, settings.player(settings.black, game::Token::Black)?]));
+    let players = match players {
+        Ok(players) => players,
+        Err(message) => {
+            exit_on_error(Err(message));
+            return;
+        }
+    };
+    let label = |index: usize| {
+        let spec = [settings.white, settings.black][index];
+        format!("#{} {spec}", index + 1)
+    };
+    interrupt_on_ctrl_c(&players[0], &players[1]);
+    let mut ui = ui::Ui::new(settings.theme, settings.verbose, settings.animation);
+
+    let mut wins = [0; 2];
+    let mut draws = 0;
+    for number in 0.. {
+        if number > 0 {
+            ui.reset();
+        }
+
+        let first = number % 2;
+        let Some(mut ending) = start(&players[first], &players[1 - first], settings, &mut ui)
+        else {
+            return;
+        };
+        if let Err(e) = save(&ending, settings, first, number) {
+            ending.message = format!("{} - Could not save the game: {e}", ending.message);
+        }
+        if settings.games == 1 {
+            ui.finish(&ending.game, &ending.message);
+            return;
+        }
+
+        match ending.winner {
+            Some(game::Token::White) => wins[first] += 1,
+            Some(game::Token::Black) => wins[1 - first] += 1,
+            None => draws += 1,
+        }
+        let counted = wins[0] + wins[1] + if settings.replay_draws { 0 } else { draws };
+        let leader = usize::from(wins[1] > wins[0]);
+        let over = wins[leader] > settings.games / 2 || counted >= settings.games;
+
+        let score = if over {
+            if wins[0] == wins[1] {
+                format!("The series is tied {}-{}", wins[0], wins[1])
+            } else {
+                format!(
+                    "{} won the series {}-{}",
+                    label(leader),
+                    wins[leader],
+                    wins[1 - leader]
+                )
             }
+        } else {
+            format!(
+                "Series: {} {}, {} {}, {draws} drawn, game {} of {} next",
+                label(0),
+                wins[0],
+                label(1),
+                wins[1],
+                counted + 1,
+                settings.games
+            )
+        };
+        ui.finish(&ending.game, &format!("{} - {score}", ending.message));
+        if over {
+            return;
         }
     }
+}
+
+// Into the records directory, if there is one, numbering the rounds of a series
+fn save<Game>(
+    ending: &Ending<Game>,
+    settings: &config::Settings,
+    first: usize,
+    number: usize,
+) -> std::io::Result<()> {
+    let Some(directory) = &settings.records else {
+        return Ok(());
+    };
+
+    let mut record = record::Record::new(settings);
+    if first == 1 {
+        std::mem::swap(&mut record.white, &mut record.black);
+    }
+    if settings.games > 1 {
+        record.round = Some(number + 1);
+    }
+    record.result = ending
+        .winner
+        .map_or(record::Outcome::Draw, record::Outcome::Win);
+    record.moves.clone_from(&ending.moves);
+    record.save(directory).map(|_| ())
+}
 
-    Result::Players(
-        white.unwrap_or_else(|| player::Player::Ai(player::Ai::new(8, verbose))),
-        black.unwrap_or_else(|| player::Player::Human),
-    )
+fn exit_on_error(result: std::result::Result<(), String>) {
+    if let Err(message) = result {
+        eprintln!("Error: {message}");
+        std::process::exit(1);
+    }
 }
 
 fn main() {
-    match parse_args() {
-        Result::Help => {
-            usage();
+    use clap::Parser;
+    let cli = cli::Cli::parse();
+    let mut settings = match config::Config::load() {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    match cli.command {
+        None => {
+            cli.play.apply(&mut settings);
+            play(&settings);
+        }
+        Some(cli::Command::Play(options)) => {
+            options.apply(&mut settings);
+            play(&settings);
+        }
+        Some(cli::Command::Config(options)) => {
+            options.apply(&mut settings);
+            print!("{settings}");
+        }
+        Some(cli::Command::Solve(position)) => {
+            position.options.apply(&mut settings);
+            let moves = position.moves.unwrap_or(cli::Moves(Vec::new()));
+            exit_on_error(engine::solve(&settings, &moves.0));
+        }
+        Some(cli::Command::Analyze(position)) => {
+            position.options.apply(&mut settings);
+            let moves = position.moves.unwrap_or(cli::Moves(Vec::new()));
+            exit_on_error(engine::analyze(&settings, &moves.0));
+        }
+        Some(cli::Command::Replay(options)) => {
+            options.options.apply(&mut settings);
+            exit_on_error(replay::run(&options.file, &settings, options.analyze));
+        }
+        Some(cli::Command::Engine(options)) => {
+            options.options.apply(&mut settings);
+            let ai = settings
+                .ai(options.ai)
+                .unwrap_or_else(|| cli::fail("--ai must be an AI player"));
+            let result = engine::run(std::io::stdin().lock(), std::io::stdout(), &settings, &ai);
+            exit_on_error(result.map_err(|e| e.to_string()));
+        }
+        Some(cli::Command::Serve(options)) => {
+            options.engine.options.apply(&mut settings);
+            if settings.ai(options.engine.ai).is_none() {
+                cli::fail("--ai must be an AI player");
+            }
+            let result = engine::serve(&options.listen, &settings, options.engine.ai);
+            exit_on_error(
+                result.map_err(|e| format!("Could not listen on {}: {e}", options.listen)),
+            );
+        }
+        Some(cli::Command::Http(options)) => {
+            options.options.apply(&mut settings);
+            if settings.ai(options.ai).is_none() {
+                cli::fail("--ai must be an AI player");
+            }
+            if settings.variant != game::Variant::Classic {
+                cli::fail("the HTTP API only plays the classic rules");
+            }
+            let listener = std::net::TcpListener::bind(&options.listen).and_then(|listener| {
+                eprintln!("Listening on {}", listener.local_addr()?);
+                Ok(listener)
+            });
+            match listener {
+                Ok(listener) => http::Server::new(settings, options.ai).serve(&listener),
+                Err(e) => {
+                    exit_on_error(Err(format!("Could not listen on {}: {e}", options.listen)));
+                }
+            }
+        }
+        Some(cli::Command::Host(options)) => {
+            options.options.apply(&mut settings);
+            if settings.variant != game::Variant::Classic {
+                cli::fail("hosted games only play the classic rules");
+            }
+            let listener = std::net::TcpListener::bind(&options.listen).and_then(|listener| {
+                eprintln!("Listening on ws://{}", listener.local_addr()?);
+                Ok(listener)
+            });
+            match listener {
+                Ok(listener) => online::Host::new(settings).serve(&listener),
+                Err(e) => {
+                    exit_on_error(Err(format!("Could not listen on {}: {e}", options.listen)));
+                }
+            }
         }
-        Result::Error => {
-            println!("Invalid arguments");
-            println!();
-            usage();
+        Some(cli::Command::Tournament(options)) => {
+            options.options.apply(&mut settings);
+            exit_on_error(tournament::run(&options.players, options.rounds, &settings));
         }
-        Result::Players(white, black) => {
-            start(&white, &black);
+        Some(cli::Command::Completions { shell }) => {
+            cli::completions(shell);
         }
     }
 }
@@ -0,0 +1,717 @@
+// Games between players anywhere, in a browser or playing remotely from the terminal, over
+// WebSocket. Messages are JSON objects with a type, and columns count from 1:
+//
+//   join     {"type": "join", "game": "f3a9c2d1", "colour": "black"}
+//            Takes a seat, in a new game if none is given, on the free side unless one is asked for
+//   rejoin   {"type": "rejoin", "game": "f3a9c2d1", "token": "…"}
+//            Takes back a seat after losing the connection, with the token given when joining
+//   move     {"type": "move", "column": 4}
+//
+// The server answers joined, with the game, colour and token, and error, with a message. It
+// pushes every move to both seats, then the state of the game:
+//
+//   move     {"type": "move", "colour": "white", "column": 4}
+//   state    {"type": "state", "game": "f3a9c2d1", "moves": [4], "board": [".X.", …],
+//             "status": "ongoing", "next": "black", "connected": {"white": true, "black": false}}
+use std::convert::TryFrom;
+
+#[derive(serde::Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+enum Incoming {
+    Join {
+        game: Option<String>,
+        colour: Option<String>,
+    },
+    Rejoin {
+        game: String,
+        token: String,
+    },
+    Move {
+        column: u8,
+    },
+}
+
+pub struct Host {
+    settings: super::config::Settings,
+    tables: std::sync::Mutex<std::collections::HashMap<String, Table>>,
+}
+
+#[derive(Default)]
+struct Table {
+    moves: Vec<super::game::Move>,
+    seats: [Seat; 2],
+    // When the last connected seat went away
+    left: Option<std::time::Instant>,
+}
+
+// Taken once it has a secret, and connected while it has somewhere to send messages
+#[derive(Default)]
+struct Seat {
+    secret: Option<String>,
+    outbox: Option<std::sync::mpsc::Sender<String>>,
+}
+
+impl Host {
+    // How long a connection waits for a message before sending what others pushed to it
+    const POLL: std::time::Duration = std::time::Duration::from_millis(50);
+    // How long a game nobody is connected to is kept for its players to come back
+    const ABANDONED: std::time::Duration = std::time::Duration::from_secs(30 * 60);
+
+    pub fn new(settings: super::config::Settings) -> Self {
+        Self {
+            settings,
+            tables: std::sync::Mutex::new(std::collections::HashMap::new()),
+        }
+    }
+
+    // Answers every connection on its own thread
+    pub fn serve(self, listener: &std::net::TcpListener) {
+        let host = std::sync::Arc::new(self);
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("Could not accept a connection: {e}");
+                    continue;
+                }
+            };
+
+            let host = host.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = host.handle(stream) {
+                    eprintln!("Connection lost: {e}");
+                }
+            });
+        }
+    }
+
+    fn handle(&self, stream: std::net::TcpStream) -> Result<(), String> {
+        use tungstenite::Message;
+
+        let mut socket = tungstenite::accept(stream).map_err(|e| e.to_string())?;
+        socket
+            .get_ref()
+            .set_read_timeout(Some(Self::POLL))
+            .map_err(|e| e.to_string())?;
+
+        let (outbox, inbox) = std::sync::mpsc::channel();
+        let mut seat = None;
+        let result = loop {
+            match socket.read() {
+                Ok(Message::Text(text)) => {
+                    let reply = self.receive(&text, &mut seat, &outbox);
+                    if let Err(e) = socket.send(Message::Text(reply.to_string())) {
+                        break Err(e);
+                    }
+                }
+                Ok(Message::Close(_)) => break Ok(()),
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(e))
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) => {}
+                Err(e) => break Err(e),
+            }
+
+            let mut failed = None;
+            for message in inbox.try_iter() {
+                if let Err(e) = socket.send(Message::Text(message)) {
+                    failed = Some(e);
+                    break;
+                }
+            }
+            if let Some(e) = failed {
+                break Err(e);
+            }
+        };
+
+        if let Some((game, token)) = seat {
+            self.leave(&game, token);
+        }
+
+        match result {
+            Ok(())
+            | Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                Ok(())
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    // Answers a message, keeping the seat it takes
+    fn receive(
+        &self,
+        text: &str,
+        seat: &mut Option<(String, super::game::Token)>,
+        outbox: &std::sync::mpsc::Sender<String>,
+    ) -> serde_json::Value {
+        let message = match serde_json::from_str::<Incoming>(text) {
+            Ok(message) => message,
+            Err(e) => return failure(&format!("the message is not understood: {e}")),
+        };
+
+        let mut tables = self.lock();
+        match message {
+            Incoming::Join { game, colour } => {
+                if seat.is_some() {
+                    return failure("the connection already has a seat");
+                }
+                self.sweep(&mut tables, std::time::Instant::now());
+                let game = match game {
+                    Some(game) if tables.contains_key(&game) => game,
+                    Some(game) => return failure(&format!("game {game} does not exist")),
+                    None => loop {
+                        let game = random(4);
+                        if let std::collections::hash_map::Entry::Vacant(entry) =
+                            tables.entry(game.clone())
+                        {
+                            entry.insert(Table::default());
+                            break game;
+                        }
+                    },
+                };
+
+                let table = tables.get_mut(&game).expect("The game exists");
+                let token = match colour.as_deref() {
+                    Some("white") => super::game::Token::White,
+                    Some("black") => super::game::Token::Black,
+                    Some(colour) => return failure(&format!("{colour} is not white or black")),
+                    None if table.seats[0].secret.is_none() => super::game::Token::White,
+                    None => super::game::Token::Black,
+                };
+                if table.seats[index(token)].secret.is_some() {
+                    return failure(&format!(
+                        "{} is taken, rejoin with its token",
+                        colour_of(token)
+                    ));
+                }
+
+                let secret = random(8);
+                table.seats[index(token)] = Seat {
+                    secret: Some(secret.clone()),
+                    outbox: Some(outbox.clone()),
+                };
+                self.push(&game, table, None);
+                *seat = Some((game.clone(), token));
+                joined(&game, token, &secret)
+            }
+            Incoming::Rejoin {
+                game,
+                token: secret,
+            } => {
+                let Some(table) = tables.get_mut(&game) else {
+                    return failure(&format!("game {game} does not exist"));
+                };
+                let Some(token) = [super::game::Token::White, super::game::Token::Black]
+                    .iter()
+                    .copied()
+                    .find(|token| table.seats[index(*token)].secret.as_ref() == Some(&secret))
+                else {
+                    return failure("the token is not the one of a seat of the game");
+                };
+
+                table.seats[index(token)].outbox = Some(outbox.clone());
+                self.push(&game, table, None);
+                *seat = Some((game.clone(), token));
+                joined(&game, token, &secret)
+            }
+            Incoming::Move { column } => {
+                let Some((game, token)) = seat else {
+                    return failure("join a game first");
+                };
+                let Some(table) = tables.get_mut(game.as_str()) else {
+                    return failure("the game is gone");
+                };
+                let next = match super::engine::ongoing(&self.settings, &table.moves) {
+                    Ok((_, next)) => next,
+                    Err(message) => return failure(&message),
+                };
+                if next != *token {
+                    return failure("it is the turn of the other player");
+                }
+                let Some(column) = column.checked_sub(1) else {
+                    return failure("columns count from 1");
+                };
+
+                table.moves.push(super::game::Move::Drop(column));
+                if let Err(message) = super::engine::position(&self.settings, &table.moves) {
+                    table.moves.pop();
+                    return failure(&message);
+                }
+                self.push(game, table, Some((*token, column)));
+                serde_json::json!({ "type": "ok" })
+            }
+        }
+    }
+
+    // The seat stays taken for its player to come back, as long as the game is kept
+    fn leave(&self, game: &str, token: super::game::Token) {
+        let mut tables = self.lock();
+        let Some(table) = tables.get_mut(game) else {
+            return;
+        };
+        table.seats[index(token)].outbox = None;
+        if table.seats.iter().all(|seat| seat.outbox.is_none()) {
+            table.left = Some(std::time::Instant::now());
+        }
+        self.push(game, table, None);
+        self.sweep(&mut tables, std::time::Instant::now());
+    }
+
+    // Forgets the games nobody is connected to, at once when they are over
+    fn sweep(
+        &self,
+        tables: &mut std::collections::HashMap<String, Table>,
+        now: std::time::Instant,
+    ) {
+        tables.retain(|_, table| match table.left {
+            Some(left) if table.seats.iter().all(|seat| seat.outbox.is_none()) => {
+                now.saturating_duration_since(left) < Self::ABANDONED
+                    && super::engine::ongoing(&self.settings, &table.moves).is_ok()
+            }
+            _ => true,
+        });
+    }
+
+    // Sends the move, if there is one, and the state of the game to whoever is connected
+    fn push(&self, game: &str, table: &Table, played: Option<(super::game::Token, u8)>) {
+        let mut messages = Vec::new();
+        if let Some((token, column)) = played {
+            messages.push(serde_json::json!({
+                "type": "move",
+                "colour": colour_of(token),
+                "column": column + 1,
+            }));
+        }
+
+        let mut state = match super::engine::position(&self.settings, &table.moves) {
+            Ok((game, token)) => super::http::state(&game, token, &table.moves),
+            Err(message) => serde_json::json!({ "error": message }),
+        };
+        state["type"] = serde_json::json!("state");
+        state["game"] = serde_json::json!(game);
+        state["connected"] = serde_json::json!({
+            "white": table.seats[0].outbox.is_some(),
+            "black": table.seats[1].outbox.is_some(),
+        });
+        messages.push(state);
+
+        for outbox in table.seats.iter().filter_map(|seat| seat.outbox.as_ref()) {
+            for message in &messages {
+                let _ = outbox.send(message.to_string());
+            }
+        }
+    }
+
+    // A panic answering another connection leaves the games as they were
+    fn lock(&self) -> std::sync::MutexGuard<'_, std::collections::HashMap<String, Table>> {
+        self.tables
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+type Socket = tungstenite::WebSocket<tungstenite::stream::MaybeTlsStream<std::net::TcpStream>>;
+
+// The player on the other side of a game hosted elsewhere, whose moves come from the state the
+// host pushes, so that those missed while joining or reconnecting are caught up with
+pub struct Remote {
+    url: String,
+    game: String,
+    secret: String,
+    token: super::game::Token,
+    socket: std::cell::RefCell<Socket>,
+    search: super::ai::Search,
+    // Known to the host, and played here
+    moves: std::cell::RefCell<Vec<u8>>,
+    played: std::cell::Cell<usize>,
+}
+
+impl Remote {
+    // Attempts at rejoining after losing the connection
+    const RETRIES: u32 = 5;
+    // How long the host has to answer joining or rejoining
+    const PATIENCE: std::time::Duration = std::time::Duration::from_secs(10);
+
+    // Takes the seat opposite to the token in the game of the url, like ws://host:4001/f3a9c2d1,
+    // or in a new game if the url has none. The board has to be the one of the settings
+    pub fn join(
+        url: &str,
+        token: super::game::Token,
+        settings: &super::config::Settings,
+    ) -> Result<Self, String> {
+        let (host, game) = match url
+            .strip_prefix("ws://")
+            .and_then(|rest| rest.split_once('/'))
+        {
+            Some((host, game)) if !game.is_empty() => (format!("ws://{host}"), Some(game)),
+            Some((host, _)) => (format!("ws://{host}"), None),
+            None => (String::from(url.trim_end_matches('/')), None),
+        };
+
+        let mut socket = connect(&host)?;
+        send(
+            &mut socket,
+            &serde_json::json!({
+                "type": "join",
+                "game": game,
+                "colour": colour_of(!token),
+            }),
+        )?;
+        let reply = expect(&mut socket, "joined")?;
+        let state = expect(&mut socket, "state")?;
+
+        let board = [settings.width, settings.height, settings.win];
+        if ["width", "height", "win"]
+            .iter()
+            .zip(board)
+            .any(|(key, value)| state[key] != value)
+        {
+            return Err(format!(
+                "the game is played with a width of {}, a height of {} and win of {}",
+                state["width"], state["height"], state["win"]
+            ));
+        }
+
+        Ok(Self {
+            url: host,
+            game: String::from(reply["game"].as_str().unwrap_or_default()),
+            secret: String::from(reply["token"].as_str().unwrap_or_default()),
+            token,
+            socket: std::cell::RefCell::new(socket),
+            search: super::ai::Search::default(),
+            moves: std::cell::RefCell::new(moves(&state)),
+            played: std::cell::Cell::new(0),
+        })
+    }
+
+    pub fn game(&self) -> &str {
+        &self.game
+    }
+
+    pub fn search(&self) -> super::ai::Search {
+        self.search.clone()
+    }
+
+    // Waits for the move of the other side, which stopping the search gives up on
+    pub fn play(&self) -> super::player::Result {
+        use super::player::Result;
+        self.search.begin();
+        let result = loop {
+            if let Some(column) = self.moves.borrow().get(self.played.get()) {
+                break Result::Ok(super::game::Move::Drop(*column));
+            }
+            if self.search.stopped() {
+                break Result::Quit;
+            }
+
+            let message = match read(&mut self.socket.borrow_mut()) {
+                Ok(Some(message)) => message,
+                Ok(None) => continue,
+                Err(e) => match self.rejoin() {
+                    Ok(()) => continue,
+                    Err(message) => break Result::Error(format!("{e}, and {message}")),
+                },
+            };
+            match message["type"].as_str() {
+                Some("state") => *self.moves.borrow_mut() = moves(&message),
+                Some("error") => {
+                    break Result::Error(format!("the host says {}", message["message"]));
+                }
+                _ => {}
+            }
+        };
+        self.search.end();
+        result
+    }
+
+    // Sends the moves of this side, rejoining once if the connection was lost
+    pub fn observe(&self, token: super::game::Token, column: u8) {
+        self.played.set(self.played.get() + 1);
+        if token == self.token {
+            return;
+        }
+        let message = serde_json::json!({ "type": "move", "column": column + 1 });
+        if send(&mut self.socket.borrow_mut(), &message).is_err() && self.rejoin().is_ok() {
+            let _ = send(&mut self.socket.borrow_mut(), &message);
+        }
+    }
+
+    fn rejoin(&self) -> Result<(), String> {
+        let mut last = String::new();
+        for _ in 0..Self::RETRIES {
+            std::thread::sleep(std::time::Duration::from_secs(1));
+            let rejoined = connect(&self.url).and_then(|mut socket| {
+                let message = serde_json::json!({
+                    "type": "rejoin",
+                    "game": self.game,
+                    "token": self.secret,
+                });
+                send(&mut socket, &message)?;
+                expect(&mut socket, "joined")?;
+                Ok(socket)
+            });
+            match rejoined {
+                Ok(socket) => {
+                    self.socket.replace(socket);
+                    return Ok(());
+                }
+                Err(message) => last = message,
+            }
+        }
+        Err(format!("could not rejoin: {last}"))
+    }
+}
+
+// Leaving the seat for later, as when the connection is lost
+impl Drop for Remote {
+    fn drop(&mut self) {
+        let socket = self.socket.get_mut();
+        let _ = socket.close(None);
+        let _ = socket.flush();
+    }
+}
+
+fn connect(url: &str) -> Result<Socket, String> {
+    let (socket, _) =
+        tungstenite::connect(url).map_err(|e| format!("could not connect to {url}: {e}"))?;
+    if let tungstenite::stream::MaybeTlsStream::Plain(stream) = socket.get_ref() {
+        stream
+            .set_read_timeout(Some(Host::POLL))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(socket)
+}
+
+fn send(socket: &mut Socket, message: &serde_json::Value) -> Result<(), String> {
+    socket
+        .send(tungstenite::Message::Text(message.to_string()))
+        .map_err(|e| e.to_string())
+}
+
+// Nothing when there was no message in time, or it was not JSON
+fn read(socket: &mut Socket) -> Result<Option<serde_json::Value>, String> {
+    match socket.read() {
+        Ok(tungstenite::Message::Text(text)) => Ok(serde_json::from_str(&text).ok()),
+        Ok(_) => Ok(None),
+        Err(tungstenite::Error::Io(e))
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+            ) =>
+        {
+            Ok(None)
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// Skips what comes before the message of the given type, failing on errors or if it is too long
+// in coming
+fn expect(socket: &mut Socket, kind: &str) -> Result<serde_json::Value, String> {
+    let deadline = std::time::Instant::now() + Remote::PATIENCE;
+    loop {
+        if std::time::Instant::now() > deadline {
+            return Err(format!("the host sent no {kind} message in time"));
+        }
+        let Some(message) = read(socket)? else {
+            continue;
+        };
+        match message["type"].as_str() {
+            Some(found) if found == kind => return Ok(message),
+            Some("error") => return Err(format!("the host says {}", message["message"])),
+            _ => {}
+        }
+    }
+}
+
+// From 0, as the game counts them
+fn moves(state: &serde_json::Value) -> Vec<u8> {
+    state["moves"]
+        .as_array()
+        .map(|moves| {
+            moves
+                .iter()
+                .filter_map(serde_json::Value::as_u64)
+                .filter_map(|column| u8::try_from(column).ok())
+                .map(|column| column.saturating_sub(1))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn joined(game: &str, token: super::game::Token, secret: &str) -> serde_json::Value {
+    serde_json::json!({
+        "type": "joined",
+        "game": game,
+        "colour": colour_of(token),
+        "token": secret,
+    })
+}
+
+fn failure(message: &str) -> serde_json::Value {
+    serde_json::json!({ "type": "error", "message": message })
+}
+
+fn index(token: super::game::Token) -> usize {
+    match token {
+        super::game::Token::White => 0,
+        super::game::Token::Black => 1,
+    }
+}
+
+fn colour_of(token: super::game::Token) -> &'static str {
+    match token {
+        super::game::Token::White => "white",
+        super::game::Token::Black => "black",
+    }
+}
+
+// Hexadecimal, from the given number of random bytes
+fn random(bytes: usize) -> String {
+    use rand::Rng;
+    use std::fmt::Write;
+    let mut rng = rand::thread_rng();
+    let mut text = String::new();
+    for _ in 0..bytes {
+        let _ = write!(text, "{:02x}", rng.gen::<u8>());
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    mod online {
+        use super::super::*;
+        use crate::game::Token;
+
+        fn settings() -> crate::config::Settings {
+            crate::config::Settings {
+                width: 4,
+                height: 4,
+                win: 3,
+                ..Default::default()
+            }
+        }
+
+        fn host() -> std::net::SocketAddr {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let address = listener.local_addr().unwrap();
+            std::thread::spawn(move || Host::new(settings()).serve(&listener));
+            address
+        }
+
+        #[test]
+        fn messages() {
+            let host = Host::new(crate::config::Settings::default());
+            let (outbox, inbox) = std::sync::mpsc::channel();
+            let mut seat = None;
+
+            let reply = host.receive(r#"{"type": "move", "column": 1}"#, &mut seat, &outbox);
+            assert_eq!(reply["type"], "error");
+
+            let reply = host.receive(r#"{"type": "join"}"#, &mut seat, &outbox);
+            assert_eq!(reply["colour"], "white");
+            let game = reply["game"].as_str().unwrap().to_string();
+            let secret = reply["token"].as_str().unwrap().to_string();
+            assert_eq!(inbox.try_iter().count(), 1);
+
+            let reply = host.receive(r#"{"type": "move", "column": 2}"#, &mut seat, &outbox);
+            assert_eq!(reply["type"], "ok");
+            let pushed = inbox.try_iter().collect::<Vec<_>>();
+            assert_eq!(pushed.len(), 2);
+            assert!(pushed[0].contains(r#""column":2"#));
+            assert!(pushed[1].contains(r#""next":"black""#));
+
+            let reply = host.receive(r#"{"type": "move", "column": 3}"#, &mut seat, &outbox);
+            assert_eq!(reply["message"], "it is the turn of the other player");
+
+            let mut other = None;
+            let join = format!(r#"{{"type": "join", "game": "{game}", "colour": "white"}}"#);
+            assert_eq!(host.receive(&join, &mut other, &outbox)["type"], "error");
+            let rejoin = format!(r#"{{"type": "rejoin", "game": "{game}", "token": "{secret}"}}"#);
+            assert_eq!(
+                host.receive(&rejoin, &mut other, &outbox)["colour"],
+                "white"
+            );
+            let rejoin = format!(r#"{{"type": "rejoin", "game": "{game}", "token": "nope"}}"#);
+            assert_eq!(host.receive(&rejoin, &mut None, &outbox)["type"], "error");
+        }
+
+        #[test]
+        fn forgotten() {
+            let host = Host::new(settings());
+            let (outbox, _inbox) = std::sync::mpsc::channel();
+            let join = |seat: &mut Option<(String, Token)>| {
+                let reply = host.receive(r#"{"type": "join"}"#, seat, &outbox);
+                reply["game"].as_str().unwrap().to_string()
+            };
+
+            // Kept for a while for its players to come back
+            let mut seat = None;
+            let abandoned = join(&mut seat);
+            host.leave(&abandoned, Token::White);
+            assert!(host.lock().contains_key(&abandoned));
+
+            // Gone as soon as nobody is connected once it is over
+            let mut seat = None;
+            let over = join(&mut seat);
+            host.lock().get_mut(&over).unwrap().moves = [0, 1, 0, 1, 0]
+                .iter()
+                .map(|column| crate::game::Move::Drop(*column))
+                .collect();
+            host.leave(&over, Token::White);
+            assert!(!host.lock().contains_key(&over));
+
+            let mut tables = host.lock();
+            host.sweep(&mut tables, std::time::Instant::now() + Host::ABANDONED);
+            assert!(!tables.contains_key(&abandoned));
+        }
+
+        #[test]
+        fn remote() {
+            let address = host();
+            let url = format!("ws://{address}");
+            let other = Remote::join(&url, Token::Black, &crate::config::Settings::default());
+            assert!(other.is_err());
+
+            // Plays white from here, against the remote black
+            let remote = Remote::join(&url, Token::Black, &settings()).unwrap();
+            let (mut browser, _) = tungstenite::connect(&url).unwrap();
+            let join = serde_json::json!({ "type": "join", "game": remote.game() });
+            send(&mut browser, &join).unwrap();
+            let joined = expect(&mut browser, "joined").unwrap();
+            assert_eq!(joined["colour"], "black");
+
+            remote.observe(Token::White, 0);
+            assert_eq!(expect(&mut browser, "move").unwrap()["column"], 1);
+
+            send(
+                &mut browser,
+                &serde_json::json!({ "type": "move", "column": 2 }),
+            )
+            .unwrap();
+            assert_eq!(
+                remote.play(),
+                crate::player::Result::Ok(crate::game::Move::Drop(1))
+            );
+            remote.observe(Token::Black, 1);
+
+            // Gone while white plays, the browser finds the game as it is when coming back
+            drop(browser);
+            remote.observe(Token::White, 2);
+            let (mut browser, _) = tungstenite::connect(&url).unwrap();
+            let rejoin = serde_json::json!({
+                "type": "rejoin",
+                "game": remote.game(),
+                "token": joined["token"],
+            });
+            send(&mut browser, &rejoin).unwrap();
+            assert_eq!(expect(&mut browser, "joined").unwrap()["colour"], "black");
+            let state = expect(&mut browser, "state").unwrap();
+            assert_eq!(state["moves"], serde_json::json!([1, 2, 3]));
+            assert_eq!(state["next"], "black");
+        }
+    }
+}
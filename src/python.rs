@@ -0,0 +1,301 @@
+// The game, the AI and the solver for Python, built by maturin with the python feature. Columns
+// and rows count from 0, rows from the top, and cells are 0 when empty, 1 for white and 2 for
+// black:
+//
+//   import connect4
+//   game = connect4.Game(7, 6, 4)
+//   game = game.place(3)  # Games do not change, placing answers the next one
+//   column, scores = connect4.solve(game, depth=8)
+//   cells = game.array()  # numpy array of height rows by width columns
+//
+// Searches let go of the interpreter, so that several threads can label positions at once
+use super::game::Game as _;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+#[pyclass(frozen)]
+#[derive(Clone)]
+pub struct Game {
+    state: super::game::Connect4,
+    token: super::game::Token,
+    moves: Vec<u8>,
+}
+
+#[pymethods]
+impl Game {
+    #[new]
+    #[pyo3(signature = (
+        width = super::game::WIDTH,
+        height = super::game::HEIGHT,
+        win = super::game::WIN,
+    ))]
+    fn new(width: u8, height: u8, win: u8) -> PyResult<Self> {
+        let sides = 1..=127;
+        let longest = width.max(height);
+        if !sides.contains(&width) || !sides.contains(&height) || win < 2 || win > longest {
+            return Err(PyValueError::new_err(format!(
+                "a board of {width} by {height} cannot have {win} in a row"
+            )));
+        }
+
+        Ok(Self {
+            state: super::game::new(width, height, win),
+            token: super::game::Token::White,
+            moves: Vec::new(),
+        })
+    }
+
+    // The game after the player to move dropped a token in the column
+    fn place(&self, column: u8) -> PyResult<Self> {
+        if self.state.status() != super::game::Status::Ongoing {
+            return Err(PyValueError::new_err(super::game::Error::Over.to_string()));
+        }
+        let state = self
+            .state
+            .place(self.token, column)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+        let mut moves = self.moves.clone();
+        moves.push(column);
+        let token = if state.status() == super::game::Status::Ongoing {
+            !self.token
+        } else {
+            self.token
+        };
+        Ok(Self {
+            state,
+            token,
+            moves,
+        })
+    }
+
+    // What the status would be after dropping a token in the column
+    fn plan(&self, column: u8) -> PyResult<&'static str> {
+        let status = self
+            .state
+            .plan(self.token, column)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(describe(status, self.token))
+    }
+
+    // ongoing, white or black once one of them won, or tie
+    #[getter]
+    fn status(&self) -> &'static str {
+        describe(self.state.status(), self.token)
+    }
+
+    // white or black, to play next
+    #[getter]
+    fn turn(&self) -> &'static str {
+        colour(self.token)
+    }
+
+    // Lists of u8 would be bytes in Python
+    #[getter]
+    fn moves(&self) -> Vec<usize> {
+        self.moves.iter().copied().map(usize::from).collect()
+    }
+
+    #[getter]
+    fn width(&self) -> u8 {
+        self.state.size()
+    }
+
+    #[getter]
+    fn height(&self) -> u8 {
+        self.state.height()
+    }
+
+    #[getter]
+    fn win(&self) -> u8 {
+        self.state.win()
+    }
+
+    // Rows of cells, from the top
+    fn board(&self) -> Vec<Vec<usize>> {
+        (0..self.state.height())
+            .map(|y| {
+                (0..self.state.size())
+                    .map(|x| usize::from(self.cell(x, y)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn array<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, numpy::PyArray2<u8>>> {
+        use numpy::PyArrayMethods;
+        // An ImportError rather than a panic without numpy
+        py.import("numpy")?;
+        let cells = (0..self.state.height())
+            .flat_map(|y| (0..self.state.size()).map(move |x| (x, y)))
+            .map(|(x, y)| self.cell(x, y))
+            .collect();
+        numpy::PyArray1::from_vec(py, cells).reshape([
+            usize::from(self.state.height()),
+            usize::from(self.state.size()),
+        ])
+    }
+
+    fn __str__(&self) -> String {
+        self.state.to_string()
+    }
+}
+
+impl Game {
+    fn cell(&self, x: u8, y: u8) -> u8 {
+        match self.state.cell(x, y) {
+            Some(super::game::Token::White) => 1,
+            Some(super::game::Token::Black) => 2,
+            None => 0,
+        }
+    }
+}
+
+// Keeps its pondering and its random choices from one move to the next, searching for one thread
+// at a time
+#[pyclass(frozen)]
+pub struct Ai {
+    ai: std::sync::Mutex<super::ai::Ai>,
+}
+
+#[pymethods]
+impl Ai {
+    // A level, from beginner to perfect, makes the mistakes a person would
+    #[new]
+    #[pyo3(signature = (depth = 8, level = None, seed = None, threads = None))]
+    fn new(
+        depth: u8,
+        level: Option<&str>,
+        seed: Option<u64>,
+        threads: Option<usize>,
+    ) -> PyResult<Self> {
+        let ai = match level {
+            Some(level) => super::ai::Ai::with_level(level.parse().map_err(|()| {
+                PyValueError::new_err(format!(
+                    "{level} is not beginner, casual, club, expert or perfect"
+                ))
+            })?),
+            None => super::ai::Ai::new(depth),
+        };
+        Ok(Self {
+            ai: std::sync::Mutex::new(ai.seeded(seed).threads(threads)),
+        })
+    }
+
+    fn best_move(&self, py: Python<'_>, game: &Game) -> PyResult<u8> {
+        ongoing(game)?;
+        Ok(py.detach(|| self.lock().best_move(&game.state, game.token).column()))
+    }
+
+    // Score of every column that can be played, after searching for the best move
+    fn scores(&self, py: Python<'_>, game: &Game) -> PyResult<Vec<(u8, i64)>> {
+        ongoing(game)?;
+        Ok(py.detach(|| {
+            let ai = self.lock();
+            ai.best_move(&game.state, game.token);
+            columns(ai.scores())
+        }))
+    }
+}
+
+impl Ai {
+    // A panic in the search of another thread does not stop this one
+    fn lock(&self) -> std::sync::MutexGuard<'_, super::ai::Ai> {
+        self.ai
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+// The best column for the player to move, searching to the given depth without mistakes, and
+// the score of every column
+#[pyfunction]
+#[pyo3(signature = (game, depth = 8))]
+fn solve(py: Python<'_>, game: &Game, depth: u8) -> PyResult<(u8, Vec<(u8, i64)>)> {
+    ongoing(game)?;
+    Ok(py.detach(|| {
+        let ai = super::ai::Ai::new(depth);
+        let column = ai.best_move(&game.state, game.token).column();
+        (column, columns(ai.scores()))
+    }))
+}
+
+#[pymodule]
+fn connect4(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Game>()?;
+    module.add_class::<Ai>()?;
+    module.add_function(wrap_pyfunction!(solve, module)?)?;
+    Ok(())
+}
+
+fn ongoing(game: &Game) -> PyResult<()> {
+    if game.state.status() == super::game::Status::Ongoing {
+        Ok(())
+    } else {
+        Err(PyValueError::new_err(super::game::Error::Over.to_string()))
+    }
+}
+
+fn describe(status: super::game::Status, token: super::game::Token) -> &'static str {
+    match status {
+        super::game::Status::Victory => colour(token),
+        super::game::Status::Defeat => colour(!token),
+        super::game::Status::Tie => "tie",
+        super::game::Status::Ongoing | super::game::Status::Timeout => "ongoing",
+    }
+}
+
+// Games here only have drops
+fn columns(scores: Vec<(super::game::Move, i64)>) -> Vec<(u8, i64)> {
+    scores
+        .into_iter()
+        .map(|(action, score)| (action.column(), score))
+        .collect()
+}
+
+fn colour(token: super::game::Token) -> &'static str {
+    match token {
+        super::game::Token::White => "white",
+        super::game::Token::Black => "black",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod python {
+        use super::super::*;
+
+        #[test]
+        fn game() {
+            assert!(Game::new(0, 6, 4).is_err());
+            assert!(Game::new(7, 6, 8).is_err());
+
+            let game = Game::new(4, 4, 3).unwrap();
+            assert_eq!((game.width(), game.height(), game.win()), (4, 4, 3));
+            assert!(game.place(4).is_err());
+            let game = [0, 3, 1]
+                .iter()
+                .fold(game, |game, column| game.place(*column).unwrap());
+            assert_eq!(game.board()[3], vec![1, 1, 0, 2]);
+            assert_eq!(game.board()[2], vec![0, 0, 0, 0]);
+            assert_eq!((game.status(), game.turn()), ("ongoing", "black"));
+            assert_eq!(game.moves(), vec![0, 3, 1]);
+
+            // Black has to block the third in a row
+            Python::initialize();
+            let (column, scores) = Python::attach(|py| solve(py, &game, 4)).unwrap();
+            assert_eq!(column, 2);
+            assert_eq!(scores.len(), 4);
+            let ai = Ai::new(4, None, Some(1), None).unwrap();
+            assert_eq!(Python::attach(|py| ai.best_move(py, &game)).unwrap(), 2);
+            assert!(Ai::new(4, Some("grandmaster"), None, None).is_err());
+
+            let game = game.place(3).unwrap();
+            assert_eq!(game.plan(2).unwrap(), "white");
+            let game = game.place(2).unwrap();
+            assert_eq!(game.status(), "white");
+            assert!(game.place(2).is_err());
+            assert!(Python::attach(|py| solve(py, &game, 4)).is_err());
+        }
+    }
+}
@@ -0,0 +1,515 @@
+// Games written like PGN: headers in brackets, then the numbered moves as columns counted from
+// 1, each optionally followed by a comment in braces, which may start with the evaluation of the
+// AI that played it, and the result:
+//
+//   [White "a6"]
+//   [Black "h"]
+//   [Date "2026.10.19"]
+//   [Width "7"]
+//   [Height "7"]
+//   [Win "4"]
+//   [Variant "popout"]
+//   [TimeControl "5m+3s"]
+//   [Result "1-0"]
+//
+//   1. 4 {[%eval 12]} 4 2. 5 3 {Blunder} 3. p4 {[%eval 2401] Player XX won} 1-0
+//
+// Pops are columns with a p in front, and the variant is only written for games that are not
+// classic
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub white: String,
+    pub black: String,
+    pub date: String,
+    pub round: Option<usize>,
+    pub width: u8,
+    pub height: u8,
+    pub win: u8,
+    pub variant: super::game::Variant,
+    pub time: Option<super::clock::Control>,
+    pub result: Outcome,
+    pub moves: Vec<Move>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    Win(super::game::Token),
+    Draw,
+    Unfinished,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Move {
+    pub played: super::game::Move,
+    pub evaluation: Option<i64>,
+    pub comment: Option<String>,
+}
+
+impl Record {
+    // An unfinished game with no moves, dated today
+    pub fn new(settings: &super::config::Settings) -> Self {
+        Self {
+            white: settings.white.to_string(),
+            black: settings.black.to_string(),
+            date: today(),
+            round: None,
+            width: settings.width,
+            height: settings.height,
+            win: settings.win,
+            variant: settings.variant,
+            time: settings.time,
+            result: Outcome::Unfinished,
+            moves: Vec::new(),
+        }
+    }
+
+    // The position after the moves, and the token to play next
+    pub fn game(&self) -> Result<(impl super::game::Game, super::game::Token), String> {
+        super::game::replay(self.start(), &self.played())
+            .map_err(|(index, e)| format!("move {} cannot be played: {e}", index + 1))
+    }
+
+    // The empty board the game started from
+    pub fn start(&self) -> super::game::AnyGame {
+        super::game::variant(self.variant, self.width, self.height, self.win)
+    }
+
+    pub fn played(&self) -> Vec<super::game::Move> {
+        self.moves.iter().map(|play| play.played).collect()
+    }
+
+    // Into a new file of the directory, named after the time it is saved
+    pub fn save(&self, directory: &std::path::Path) -> std::io::Result<std::path::PathBuf> {
+        use std::io::Write;
+        std::fs::create_dir_all(directory)?;
+
+        let stamp = timestamp();
+        for attempt in 1.. {
+            let name = if attempt == 1 {
+                format!("{stamp}.c4n")
+            } else {
+                format!("{stamp}-{attempt}.c4n")
+            };
+            let path = directory.join(name);
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    write!(file, "{self}")?;
+                    return Ok(path);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e),
+            }
+        }
+        unreachable!("Ran out of file names")
+    }
+
+    fn header(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let size = |value: &str| {
+            value
+                .parse::<u8>()
+                .ok()
+                .filter(|size| (1..=super::config::Settings::MAX_SIZE).contains(size))
+                .ok_or_else(|| format!("{key} is not a size: {value}"))
+        };
+
+        match key {
+            "White" => self.white = String::from(value),
+            "Black" => self.black = String::from(value),
+            "Date" => self.date = String::from(value),
+            "Round" => {
+                self.round = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Round is not a number: {value}"))?,
+                );
+            }
+            "Width" => self.width = size(value)?,
+            "Height" => self.height = size(value)?,
+            "Win" => self.win = size(value)?,
+            "Variant" => {
+                self.variant = value
+                    .parse()
+                    .map_err(|()| format!("Variant is not known: {value}"))?;
+            }
+            "TimeControl" => {
+                self.time = Some(
+                    value
+                        .parse()
+                        .map_err(|()| format!("TimeControl is not a time control: {value}"))?,
+                );
+            }
+            "Result" => {
+                self.result = value
+                    .parse()
+                    .map_err(|()| format!("Result is not a result: {value}"))?;
+            }
+            // Like in PGN, headers of other programs are no reason to refuse a game
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Record {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(fmt, "[White \"{}\"]", self.white)?;
+        writeln!(fmt, "[Black \"{}\"]", self.black)?;
+        writeln!(fmt, "[Date \"{}\"]", self.date)?;
+        if let Some(round) = self.round {
+            writeln!(fmt, "[Round \"{round}\"]")?;
+        }
+        writeln!(fmt, "[Width \"{}\"]", self.width)?;
+        writeln!(fmt, "[Height \"{}\"]", self.height)?;
+        writeln!(fmt, "[Win \"{}\"]", self.win)?;
+        if self.variant != super::game::Variant::Classic {
+            writeln!(fmt, "[Variant \"{}\"]", self.variant)?;
+        }
+        if let Some(time) = self.time {
+            writeln!(fmt, "[TimeControl \"{time}\"]")?;
+        }
+        writeln!(fmt, "[Result \"{}\"]", self.result)?;
+        writeln!(fmt)?;
+
+        // Lines are wrapped before they grow longer than 80 characters
+        let mut line = String::new();
+        let mut words = Vec::new();
+        for (index, play) in self.moves.iter().enumerate() {
+            if index % 2 == 0 {
+                words.push(format!("{}.", index / 2 + 1));
+            }
+            words.push(play.played.to_string());
+
+            let comment = match (play.evaluation, &play.comment) {
+                (Some(evaluation), Some(comment)) => {
+                    Some(format!("[%eval {evaluation}] {comment}"))
+                }
+                (Some(evaluation), None) => Some(format!("[%eval {evaluation}]")),
+                (None, Some(comment)) => Some(comment.clone()),
+                (None, None) => None,
+            };
+            if let Some(comment) = comment {
+                words.push(format!("{{{comment}}}"));
+            }
+        }
+        words.push(self.result.to_string());
+
+        for word in words {
+            if !line.is_empty() && line.len() + word.len() >= 80 {
+                writeln!(fmt, "{line}")?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&word);
+        }
+        writeln!(fmt, "{line}")
+    }
+}
+
+impl std::str::FromStr for Record {
+    type Err = String;
+
+    // Checks that the moves can be played
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        let mut record = Self {
+            white: String::from("?"),
+            black: String::from("?"),
+            date: String::from("????.??.??"),
+            round: None,
+            width: super::game::WIDTH,
+            height: super::game::HEIGHT,
+            win: super::game::WIN,
+            variant: super::game::Variant::Classic,
+            time: None,
+            result: Outcome::Unfinished,
+            moves: Vec::new(),
+        };
+
+        let mut lines = text.lines().enumerate().peekable();
+        while let Some((number, line)) = lines.next_if(|(_, line)| {
+            let line = line.trim();
+            line.is_empty() || line.starts_with('[')
+        }) {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+                .and_then(|line| line.split_once(' '))
+                .and_then(|(key, value)| {
+                    Some((key, value.trim().strip_prefix('"')?.strip_suffix('"')?))
+                })
+                .ok_or_else(|| format!("line {} is not a header: {line}", number + 1))?;
+            record.header(key, value)?;
+        }
+
+        let movetext = lines.map(|(_, line)| line).collect::<Vec<_>>().join("\n");
+        let mut rest = movetext.trim_start();
+        let mut result = None;
+        while !rest.is_empty() {
+            if result.is_some() {
+                return Err(format!("nothing may follow the result: {rest}"));
+            }
+
+            if let Some(comment) = rest.strip_prefix('{') {
+                let end = comment.find('}').ok_or("a comment is not closed")?;
+                let play = record
+                    .moves
+                    .last_mut()
+                    .ok_or("a comment comes before the first move")?;
+                comment_on(play, comment[..end].trim())?;
+                rest = comment[end + 1..].trim_start();
+                continue;
+            }
+
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '{')
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            rest = rest[end..].trim_start();
+
+            if let Ok(outcome) = word.parse::<Outcome>() {
+                result = Some(outcome);
+            } else if word.ends_with('.') {
+                let number = word.trim_end_matches('.');
+                if number.parse::<usize>().ok() != Some(record.moves.len() / 2 + 1) {
+                    return Err(format!("move number {word} is out of sequence"));
+                }
+            } else {
+                let played = word
+                    .parse()
+                    .map_err(|()| format!("{word} is not a column counted from 1"))?;
+                record.moves.push(Move {
+                    played,
+                    evaluation: None,
+                    comment: None,
+                });
+            }
+        }
+
+        if let Some(result) = result {
+            if result != record.result {
+                return Err(format!(
+                    "the result {result} differs from the header {}",
+                    record.result
+                ));
+            }
+        }
+        if record.win < 2 || record.win > record.width.max(record.height) {
+            return Err(format!("a line of {} cannot fit on the board", record.win));
+        }
+        record.game()?;
+        Ok(record)
+    }
+}
+
+fn comment_on(play: &mut Move, comment: &str) -> Result<(), String> {
+    let comment = match comment.strip_prefix("[%eval ") {
+        Some(evaluated) => {
+            let end = evaluated.find(']').ok_or("an evaluation is not closed")?;
+            let evaluation = &evaluated[..end];
+            play.evaluation = Some(
+                evaluation
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("{evaluation} is not an evaluation"))?,
+            );
+            evaluated[end + 1..].trim()
+        }
+        None => comment,
+    };
+    if !comment.is_empty() {
+        play.comment = Some(String::from(comment));
+    }
+    Ok(())
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Win(super::game::Token::White) => write!(fmt, "1-0"),
+            Self::Win(super::game::Token::Black) => write!(fmt, "0-1"),
+            Self::Draw => write!(fmt, "1/2-1/2"),
+            Self::Unfinished => write!(fmt, "*"),
+        }
+    }
+}
+
+impl std::str::FromStr for Outcome {
+    type Err = ();
+
+    fn from_str(string: &str) -> std::result::Result<Self, Self::Err> {
+        match string {
+            "1-0" => Ok(Self::Win(super::game::Token::White)),
+            "0-1" => Ok(Self::Win(super::game::Token::Black)),
+            "1/2-1/2" => Ok(Self::Draw),
+            "*" => Ok(Self::Unfinished),
+            _ => Err(()),
+        }
+    }
+}
+
+// Where records go unless configured otherwise: $XDG_DATA_HOME/connect4/games, or
+// ~/.local/share/connect4/games
+pub fn directory() -> Option<std::path::PathBuf> {
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .map(std::path::PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .map(|home| std::path::Path::new(&home).join(".local").join("share"))
+        })?;
+    Some(base.join("connect4").join("games"))
+}
+
+fn since_epoch() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn today() -> String {
+    let (year, month, day) = civil(since_epoch() / 86_400);
+    format!("{year}.{month:02}.{day:02}")
+}
+
+// Like 20261019-123005, in UTC
+fn timestamp() -> String {
+    let seconds = since_epoch();
+    let (year, month, day) = civil(seconds / 86_400);
+    let time = seconds % 86_400;
+    format!(
+        "{year}{month:02}{day:02}-{:02}{:02}{:02}",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+// Year, month and day of a number of days since 1970-01-01, from Howard Hinnant's
+// civil_from_days
+fn civil(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    mod record {
+        use super::super::*;
+        use crate::game::{Token, Variant};
+
+        fn record() -> Record {
+            Record {
+                white: String::from("a6"),
+                black: String::from("h"),
+                date: String::from("2026.10.19"),
+                round: Some(2),
+                width: 7,
+                height: 6,
+                win: 4,
+                variant: Variant::Classic,
+                time: Some("5m+3s".parse().unwrap()),
+                result: Outcome::Win(Token::White),
+                moves: [3, 3, 4, 4, 5, 5, 6]
+                    .iter()
+                    .map(|column| Move {
+                        played: crate::game::Move::Drop(*column),
+                        evaluation: None,
+                        comment: None,
+                    })
+                    .collect(),
+            }
+        }
+
+        #[test]
+        fn round_trip() {
+            let mut record = record();
+            record.moves[0].evaluation = Some(-12);
+            record.moves[3].comment = Some(String::from("Blunder"));
+            record.moves[6].evaluation = Some(2401);
+            record.moves[6].comment = Some(String::from("Player XX won"));
+
+            let text = record.to_string();
+            assert!(text.contains("[TimeControl \"5m+3s\"]"));
+            assert!(!text.contains("[Variant"));
+            assert!(text.ends_with(
+                "1. 4 {[%eval -12]} 4 2. 5 5 {Blunder} 3. 6 6 4. 7 {[%eval 2401] Player XX won}\n1-0\n"
+            ));
+            assert_eq!(text.parse::<Record>(), Ok(record));
+        }
+
+        #[test]
+        fn parse() {
+            let record = "[White \"h\"]\n[Site \"Lunch\"]\n\n1. 4 4\n2. 4 {[%eval 3]}"
+                .parse::<Record>()
+                .unwrap();
+            assert_eq!(record.white, "h");
+            assert_eq!(record.black, "?");
+            assert_eq!(record.played(), vec![crate::game::Move::Drop(3); 3]);
+            assert_eq!(record.moves[2].evaluation, Some(3));
+            assert_eq!(record.result, Outcome::Unfinished);
+
+            for text in &[
+                "[Width \"17\"]",
+                "[Win \"9\"]",
+                "[Result \"2-0\"]",
+                "[White h]",
+                "1. 8",
+                "1. 0",
+                "2. 4",
+                "{Early} 1. 4",
+                "1. 4 {Open",
+                "1. 4 * 4",
+                "[Result \"1-0\"]\n1. 4 0-1",
+                "[Height \"1\"]\n1. 4 4",
+                "[Variant \"pop\"]",
+                "1. 4 4 2. p4",
+            ] {
+                assert!(text.parse::<Record>().is_err(), "{}", text);
+            }
+        }
+
+        #[test]
+        fn pops() {
+            let text = "[Variant \"popout\"]\n\n1. 4 4 2. 5 p4";
+            assert!(text.parse::<Record>().is_err());
+
+            let record = "[Variant \"popout\"]\n\n1. 4 5 2. p4"
+                .parse::<Record>()
+                .unwrap();
+            assert_eq!(record.variant, Variant::PopOut);
+            assert_eq!(record.moves[2].played, crate::game::Move::Pop(3));
+            let text = record.to_string();
+            assert!(text.contains("[Variant \"popout\"]"));
+            assert!(text.contains("1. 4 5 2. p4 *"));
+        }
+
+        #[test]
+        fn civil() {
+            assert_eq!(super::super::civil(0), (1970, 1, 1));
+            assert_eq!(super::super::civil(11_016), (2000, 2, 29));
+            assert_eq!(super::super::civil(20_745), (2026, 10, 19));
+        }
+    }
+}
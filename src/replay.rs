@@ -0,0 +1,250 @@
+use crossterm::{cursor, event, execute, queue, terminal};
+
+enum Step {
+    Next,
+    Previous,
+    Start,
+    End,
+    Go(usize),
+    Quit,
+}
+
+// Steps through a saved game, reading keys on a terminal and commands on lines otherwise.
+// Analyses of the positions are kept, as the AI takes a while on each
+pub fn run(
+    path: &std::path::Path,
+    settings: &super::config::Settings,
+    analyze: bool,
+) -> Result<(), String> {
+    use super::game::Game;
+    use std::io::IsTerminal;
+
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {e}", path.display()))?;
+    let record = text
+        .parse::<super::record::Record>()
+        .map_err(|e| format!("Could not read the game in {}: {e}", path.display()))?;
+
+    let mut positions = vec![record.start()];
+    let mut token = super::game::Token::White;
+    for play in &record.moves {
+        let next = positions[positions.len() - 1]
+            .play(token, play.played)
+            .map_err(|e| e.to_string())?;
+        positions.push(next);
+        token = !token;
+    }
+
+    let ai = super::engine::analyst(settings);
+    let mut cache = std::collections::HashMap::new();
+    let keys = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+    let mut index = 0;
+
+    loop {
+        let mut screen = describe(&record, index, &positions[index]);
+        if analyze && positions[index].status() == super::game::Status::Ongoing {
+            let analysis = cache.entry(index).or_insert_with(|| {
+                let action = ai.best_move(&positions[index], turn(index));
+                let mut lines = ai.analysis();
+                lines.push(format!("Best move: {action}"));
+                lines
+            });
+            for line in analysis.iter() {
+                screen.push_str(line);
+                screen.push('\n');
+            }
+        }
+
+        let step = if keys {
+            screen.push_str("[←/→ or h/l to step, Home/End to jump, q to quit]");
+            key(&screen)
+        } else {
+            println!("{screen}");
+            line()
+        }
+        .map_err(|e| e.to_string())?;
+
+        let last = positions.len() - 1;
+        index = match step {
+            Step::Next => (index + 1).min(last),
+            Step::Previous => index.saturating_sub(1),
+            Step::Start => 0,
+            Step::End => last,
+            Step::Go(index) => index.min(last),
+            Step::Quit => return Ok(()),
+        };
+    }
+}
+
+fn turn(index: usize) -> super::game::Token {
+    if index % 2 == 0 {
+        super::game::Token::White
+    } else {
+        super::game::Token::Black
+    }
+}
+
+// Headers, the move that led to the position with what was said about it, the board and who
+// is to play
+fn describe<Game: super::game::Game>(
+    record: &super::record::Record,
+    index: usize,
+    game: &Game,
+) -> String {
+    use super::game::Token;
+    use std::fmt::Write;
+
+    let mut text = String::new();
+    let _ = writeln!(
+        text,
+        "{} {} vs {} {}, {}, {}",
+        Token::White,
+        record.white,
+        Token::Black,
+        record.black,
+        record.date,
+        record.result
+    );
+
+    if index == 0 {
+        let _ = writeln!(text, "Start of {} moves", record.moves.len());
+    } else {
+        let play = &record.moves[index - 1];
+        let _ = write!(
+            text,
+            "Move {index} of {}: {} played {}",
+            record.moves.len(),
+            turn(index - 1),
+            play.played
+        );
+        if let Some(evaluation) = play.evaluation {
+            let _ = write!(text, ", evaluated {evaluation}");
+        }
+        if let Some(comment) = &play.comment {
+            let _ = write!(text, " ({comment})");
+        }
+        text.push('\n');
+    }
+
+    let _ = write!(text, "{game}");
+    let _ = writeln!(
+        text,
+        "{}",
+        match game.status() {
+            super::game::Status::Victory => format!("{} won", turn(index - 1)),
+            super::game::Status::Defeat => format!("{} won", turn(index)),
+            super::game::Status::Tie => String::from("Draw"),
+            super::game::Status::Ongoing | super::game::Status::Timeout => {
+                format!("{} to play", turn(index))
+            }
+        }
+    );
+    text
+}
+
+// Draws the screen in raw mode, where lines need a carriage return, until a key is pressed
+fn key(screen: &str) -> std::io::Result<Step> {
+    use event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+    use std::io::Write;
+
+    terminal::enable_raw_mode()?;
+    let step = (|| {
+        let mut out = std::io::stdout();
+        queue!(
+            out,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+        write!(out, "{}", screen.replace('\n', "\r\n"))?;
+        out.flush()?;
+
+        loop {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                return Ok(match key.code {
+                    KeyCode::Right | KeyCode::Char('l' | 'n' | ' ') | KeyCode::Enter => Step::Next,
+                    KeyCode::Left | KeyCode::Char('h' | 'p') | KeyCode::Backspace => Step::Previous,
+                    KeyCode::Home | KeyCode::Char('g') => Step::Start,
+                    KeyCode::End | KeyCode::Char('G') => Step::End,
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        Step::Quit
+                    }
+                    KeyCode::Char('q') | KeyCode::Esc => Step::Quit,
+                    _ => continue,
+                });
+            }
+        }
+    })();
+
+    let _ = terminal::disable_raw_mode();
+    if matches!(step, Ok(Step::Quit)) {
+        let _ = execute!(std::io::stdout(), cursor::MoveToNextLine(1));
+    }
+    step
+}
+
+// n or nothing for the next move, p for the previous one, s and e for the start and the end,
+// a number to go to a move, and q to quit, as does the end of the input
+fn line() -> std::io::Result<Step> {
+    use std::io::Write;
+    print!("[n]ext, [p]revious, [s]tart, [e]nd, move number or [q]uit: ");
+    std::io::stdout().flush()?;
+
+    loop {
+        let mut buffer = String::new();
+        if std::io::stdin().read_line(&mut buffer)? == 0 {
+            println!();
+            return Ok(Step::Quit);
+        }
+
+        let step = match buffer.trim() {
+            "" | "n" => Step::Next,
+            "p" => Step::Previous,
+            "s" => Step::Start,
+            "e" => Step::End,
+            "q" => Step::Quit,
+            number => {
+                if let Ok(index) = number.parse() {
+                    Step::Go(index)
+                } else {
+                    print!("Unknown command {number}, try again: ");
+                    std::io::stdout().flush()?;
+                    continue;
+                }
+            }
+        };
+        return Ok(step);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod replay {
+        use super::super::*;
+
+        #[test]
+        fn positions() {
+            use crate::game::Game;
+            let record =
+                "[White \"a6\"]\n[Black \"h\"]\n[Width \"4\"]\n[Height \"2\"]\n[Win \"2\"]\n\
+                          [Result \"1-0\"]\n\n1. 1 {[%eval 7]} 4 2. 2 {Won} 1-0"
+                    .parse::<crate::record::Record>()
+                    .unwrap();
+            let (game, _) = record.game().unwrap();
+
+            let text = describe(&record, 3, &game);
+            let lines = text.lines().collect::<Vec<_>>();
+            assert_eq!(lines[1], "Move 3 of 3: \u{2593}\u{2593} played 2 (Won)");
+            assert_eq!(lines.len(), 7);
+            assert_eq!(lines[6], "\u{2593}\u{2593} won");
+            assert_eq!(game.status(), crate::game::Status::Victory);
+
+            let start = crate::game::new(4, 2, 2);
+            let text = describe(&record, 0, &start);
+            assert!(text.contains("Start of 3 moves"));
+            assert!(text.ends_with("\u{2593}\u{2593} to play\n"));
+        }
+    }
+}
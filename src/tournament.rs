@@ -0,0 +1,147 @@
+#[derive(Clone, Default)]
+struct Standing {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+}
+
+impl Standing {
+    fn points(&self) -> u32 {
+        self.wins * 2 + self.draws
+    }
+}
+
+// Every AI plays every other once with each colour per round, ranked by two points per win
+// and one per draw
+pub fn run(
+    players: &[super::config::Spec],
+    rounds: u32,
+    settings: &super::config::Settings,
+) -> Result<(), String> {
+    let ais = players
+        .iter()
+        .map(|spec| settings.ai(*spec))
+        .collect::<Option<Vec<_>>>()
+        .ok_or("only AIs can take part in a tournament")?;
+    let label = |index: usize| format!("#{} {}", index + 1, players[index]);
+
+    let mut standings = vec![Standing::default(); players.len()];
+    let mut number = 0;
+    for _ in 0..rounds {
+        for white in 0..ais.len() {
+            for black in (0..ais.len()).filter(|black| *black != white) {
+                number += 1;
+                let (winner, record) = play(&ais[white], &ais[black], settings);
+                let moves = record.len();
+                if let Err(e) = save(
+                    settings,
+                    [players[white], players[black]],
+                    number,
+                    winner,
+                    record,
+                ) {
+                    eprintln!("Could not save game {number}: {e}");
+                }
+                let outcome = match winner {
+                    Some(super::game::Token::White) => {
+                        standings[white].wins += 1;
+                        standings[black].losses += 1;
+                        format!("{} won in {moves} moves", label(white))
+                    }
+                    Some(super::game::Token::Black) => {
+                        standings[black].wins += 1;
+                        standings[white].losses += 1;
+                        format!("{} won in {moves} moves", label(black))
+                    }
+                    None => {
+                        standings[white].draws += 1;
+                        standings[black].draws += 1;
+                        format!("draw in {moves} moves")
+                    }
+                };
+                println!(
+                    "{number:>3}. {} vs {}: {outcome}",
+                    label(white),
+                    label(black)
+                );
+            }
+        }
+    }
+
+    let mut ranking = standings.iter().enumerate().collect::<Vec<_>>();
+    ranking.sort_by_key(|(_, standing)| std::cmp::Reverse(standing.points()));
+    println!();
+    println!("Standings:");
+    for (index, standing) in ranking {
+        println!(
+            "  {:<16} {:>3} points  {} wins  {} draws  {} losses",
+            label(index),
+            standing.points(),
+            standing.wins,
+            standing.draws,
+            standing.losses
+        );
+    }
+    Ok(())
+}
+
+// Into the records directory, if there is one, numbered as in the tournament
+fn save(
+    settings: &super::config::Settings,
+    [white, black]: [super::config::Spec; 2],
+    number: usize,
+    winner: Option<super::game::Token>,
+    moves: Vec<super::record::Move>,
+) -> std::io::Result<()> {
+    let Some(directory) = &settings.records else {
+        return Ok(());
+    };
+
+    let mut record = super::record::Record::new(settings);
+    record.white = white.to_string();
+    record.black = black.to_string();
+    record.round = Some(number);
+    record.result = winner.map_or(super::record::Outcome::Draw, super::record::Outcome::Win);
+    record.moves = moves;
+    record.save(directory).map(|_| ())
+}
+
+// The winner, if any, and the moves played. An AI that cannot play loses
+fn play(
+    white: &super::ai::Ai,
+    black: &super::ai::Ai,
+    settings: &super::config::Settings,
+) -> (Option<super::game::Token>, Vec<super::record::Move>) {
+    use super::game::Game;
+    let mut game = super::game::variant(
+        settings.variant,
+        settings.width,
+        settings.height,
+        settings.win,
+    );
+    let mut token = super::game::Token::White;
+    let mut moves = Vec::new();
+
+    loop {
+        let ai = match token {
+            super::game::Token::White => white,
+            super::game::Token::Black => black,
+        };
+        let played = ai.best_move(&game, token);
+        match game.play(token, played) {
+            Ok(next) => game = next,
+            Err(_) => return (Some(!token), moves),
+        }
+        moves.push(super::record::Move {
+            played,
+            evaluation: ai.evaluation(),
+            comment: None,
+        });
+        match game.status() {
+            super::game::Status::Victory => return (Some(token), moves),
+            super::game::Status::Defeat => return (Some(!token), moves),
+            super::game::Status::Tie => return (None, moves),
+            super::game::Status::Ongoing | super::game::Status::Timeout => token = !token,
+        }
+    }
+}
@@ -0,0 +1,127 @@
+mod plain;
+mod terminal;
+mod theme;
+
+pub use theme::Theme;
+
+pub enum Ui {
+    Plain(plain::Plain),
+    Terminal(terminal::Terminal),
+}
+
+impl Ui {
+    // Full-screen when attached to a terminal, falling back to plain lines otherwise,
+    // in which case there is no animation
+    pub fn new(theme: Theme, verbose: bool, animation: Option<std::time::Duration>) -> Self {
+        use std::io::IsTerminal;
+        if std::io::stdin().is_terminal() && std::io::stdout().is_terminal() {
+            match terminal::Terminal::new(theme, animation) {
+                Ok(terminal) => return Self::Terminal(terminal),
+                Err(e) => eprintln!("Could not start the terminal UI: {e}"),
+            }
+        }
+        Self::Plain(plain::Plain::new(theme, verbose))
+    }
+
+    // Name of the token in messages
+    pub fn label(&self, token: super::game::Token) -> String {
+        match self {
+            Self::Plain(plain) => plain.label(token),
+            Self::Terminal(terminal) => terminal.label(token),
+        }
+    }
+
+    pub fn draw<Game: super::game::Game>(&mut self, game: &Game) {
+        match self {
+            Self::Plain(plain) => plain.draw(game),
+            Self::Terminal(terminal) => terminal.draw(game),
+        }
+    }
+
+    pub fn select<Game: super::game::Game>(
+        &mut self,
+        game: &Game,
+        token: super::game::Token,
+    ) -> super::player::Result {
+        match self {
+            Self::Plain(plain) => plain.select(game, token),
+            Self::Terminal(terminal) => terminal.select(game, token),
+        }
+    }
+
+    pub fn think(
+        &mut self,
+        token: super::game::Token,
+        search: &super::ai::Search,
+        play: impl FnOnce() -> super::player::Result,
+    ) -> super::player::Result {
+        match self {
+            Self::Plain(_) => plain::Plain::think(play),
+            Self::Terminal(terminal) => terminal.think(token, search, play),
+        }
+    }
+
+    // For the move of a player elsewhere, which stopping the search gives up on
+    pub fn wait(
+        &mut self,
+        token: super::game::Token,
+        game: &str,
+        search: &super::ai::Search,
+        play: impl FnOnce() -> super::player::Result,
+    ) -> super::player::Result {
+        match self {
+            Self::Plain(plain) => plain.wait(token, game, play),
+            Self::Terminal(terminal) => terminal.wait(token, game, search, play),
+        }
+    }
+
+    pub fn analysis(&mut self, analysis: Vec<String>) {
+        match self {
+            Self::Plain(plain) => plain.analysis(analysis),
+            Self::Terminal(terminal) => terminal.analysis(analysis),
+        }
+    }
+
+    // Keeps a copy of the clock to show next to the board
+    pub fn clock(&mut self, clock: &super::clock::Clock) {
+        match self {
+            Self::Plain(plain) => plain.clock(clock),
+            Self::Terminal(terminal) => terminal.clock(clock),
+        }
+    }
+
+    pub fn error(&mut self, message: String) {
+        match self {
+            Self::Plain(plain) => plain.error(message),
+            Self::Terminal(terminal) => terminal.error(message),
+        }
+    }
+
+    // Takes the game as it was before the move
+    pub fn record<Game: super::game::Game>(
+        &mut self,
+        game: &Game,
+        token: super::game::Token,
+        action: super::game::Move,
+    ) {
+        match self {
+            Self::Plain(_) => {}
+            Self::Terminal(terminal) => terminal.record(game, token, action),
+        }
+    }
+
+    // Makes way for the next game of a series
+    pub fn reset(&mut self) {
+        match self {
+            Self::Plain(plain) => plain.reset(),
+            Self::Terminal(terminal) => terminal.reset(),
+        }
+    }
+
+    pub fn finish<Game: super::game::Game>(&mut self, game: &Game, message: &str) {
+        match self {
+            Self::Plain(plain) => plain.finish(game, message),
+            Self::Terminal(terminal) => terminal.finish(game, message),
+        }
+    }
+}
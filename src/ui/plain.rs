@@ -0,0 +1,179 @@
+// Prints each board below the previous one, without moving the cursor, for pipes and terminals
+// that cannot be drawn on
+pub struct Plain {
+    theme: super::Theme,
+    verbose: bool,
+    error: Option<String>,
+    analysis: Vec<String>,
+    clock: Option<super::super::clock::Clock>,
+    // Read on a thread of their own once asked for, so that the clock runs out while waiting
+    lines: Option<std::sync::mpsc::Receiver<std::io::Result<String>>>,
+}
+
+impl Plain {
+    pub fn new(theme: super::Theme, verbose: bool) -> Self {
+        Self {
+            theme,
+            verbose,
+            error: None,
+            analysis: Vec::new(),
+            clock: None,
+            lines: None,
+        }
+    }
+
+    pub fn draw<Game: super::super::game::Game>(&mut self, game: &Game) {
+        println!("{}", self.theme.board(game));
+
+        if let Some(clock) = &self.clock {
+            use super::super::game::Token;
+            println!(
+                "{} {}  {} {}",
+                self.theme.token(Token::White),
+                clock.format(Token::White),
+                self.theme.token(Token::Black),
+                clock.format(Token::Black)
+            );
+        }
+
+        for line in self.analysis.drain(..) {
+            println!("{line}");
+        }
+
+        if let Some(message) = self.error.take() {
+            println!("Error: {message}");
+        }
+    }
+
+    pub fn select<Game: super::super::game::Game>(
+        &mut self,
+        game: &Game,
+        token: super::super::game::Token,
+    ) -> super::super::player::Result {
+        use super::super::game::Move;
+        use super::super::player::Result;
+        {
+            use std::io::Write;
+            if game.gravity() {
+                print!("Select the column for {}", self.theme.token(token));
+            } else {
+                print!("Select the column,row for {}", self.theme.token(token));
+            }
+            if (0..game.size()).any(|x| game.allows(token, Move::Pop(x))) {
+                print!(", p and the column to pop it");
+            }
+            print!(": ");
+            let _ = std::io::stdout().flush();
+        }
+
+        let lines = self.lines.get_or_insert_with(read_lines);
+        let read = loop {
+            let Some(clock) = &self.clock else {
+                break lines.recv();
+            };
+            if clock.expired() {
+                println!();
+                return Result::Repeat;
+            }
+            match lines.recv_timeout(std::time::Duration::from_millis(100)) {
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+                read => break read.map_err(|_| std::sync::mpsc::RecvError),
+            }
+        };
+
+        // The end of the input quits, rather than asking again forever
+        let mut buffer = match read {
+            Ok(Ok(line)) if !line.is_empty() => line,
+            Ok(Ok(_)) | Err(_) => {
+                println!();
+                return Result::Quit;
+            }
+            Ok(Err(e)) => return Result::Error(e.to_string()),
+        };
+
+        buffer = buffer.trim().to_string();
+        if buffer.is_empty() {
+            return Result::Repeat;
+        }
+
+        let (pop, column) = match buffer.strip_prefix('p') {
+            Some(column) => (true, column),
+            None => (false, buffer.as_str()),
+        };
+        match buffer.as_str() {
+            "Q" | "q" => Result::Quit,
+            _ if !game.gravity() => buffer.parse().map_or_else(
+                |()| Result::Error(String::from("expected a column and a row, like 4,2")),
+                Result::Ok,
+            ),
+            _ => match column.parse::<u8>() {
+                Ok(0) => Result::Error(super::super::game::Error::OutOfBounds.to_string()),
+                Ok(i) if pop => Result::Ok(Move::Pop(i - 1)),
+                Ok(i) => Result::Ok(Move::Drop(i - 1)),
+                Err(e) => Result::Error(e.to_string()),
+            },
+        }
+    }
+
+    pub fn label(&self, token: super::super::game::Token) -> String {
+        self.theme.token(token)
+    }
+
+    pub fn think(
+        play: impl FnOnce() -> super::super::player::Result,
+    ) -> super::super::player::Result {
+        println!();
+        play()
+    }
+
+    pub fn wait(
+        &self,
+        token: super::super::game::Token,
+        game: &str,
+        play: impl FnOnce() -> super::super::player::Result,
+    ) -> super::super::player::Result {
+        println!("Waiting for {} to play in game {game}", self.label(token));
+        play()
+    }
+
+    pub fn analysis(&mut self, analysis: Vec<String>) {
+        if self.verbose {
+            self.analysis = analysis;
+        }
+    }
+
+    pub fn clock(&mut self, clock: &super::super::clock::Clock) {
+        self.clock = Some(clock.clone());
+    }
+
+    pub fn error(&mut self, message: String) {
+        self.error = Some(message);
+    }
+
+    pub fn finish<Game: super::super::game::Game>(&mut self, game: &Game, message: &str) {
+        self.error = None;
+        self.draw(game);
+        println!("{message}");
+    }
+
+    // Leaves a blank line between the finished game and the next one
+    pub fn reset(&mut self) {
+        println!();
+        self.error = None;
+        self.analysis.clear();
+    }
+}
+
+// Sends each line of the input, then an empty one at its end
+fn read_lines() -> std::sync::mpsc::Receiver<std::io::Result<String>> {
+    let (sender, lines) = std::sync::mpsc::channel();
+    std::thread::spawn(move || loop {
+        let mut line = String::new();
+        let read = std::io::stdin().read_line(&mut line).map(|_| line);
+        let more = matches!(&read, Ok(line) if !line.is_empty());
+        if sender.send(read).is_err() || !more {
+            break;
+        }
+    });
+    lines
+}
@@ -0,0 +1,672 @@
+use crossterm::{cursor, event, execute, queue, style, terminal};
+use std::convert::TryFrom;
+
+// Full-screen UI on the alternate screen buffer, restoring the terminal when dropped
+pub struct Terminal {
+    screen: Screen,
+    column: Option<u8>,
+    row: Option<u8>,
+    animation: Option<std::time::Duration>,
+}
+
+struct Screen {
+    theme: super::Theme,
+    cells: Vec<Vec<Option<super::super::game::Token>>>,
+    size: u8,
+    height: u8,
+    win: u8,
+    // Whether tokens are dropped, rather than put on a cell the row cursor also points at
+    gravity: bool,
+    cursor: Option<u8>,
+    row: Option<u8>,
+    hover: Option<u8>,
+    last: Option<(u8, u8)>,
+    falling: Option<(super::super::game::Token, u8, u8)>,
+    status: String,
+    error: Option<String>,
+    history: Vec<(super::super::game::Token, super::super::game::Move)>,
+    // Tokens taken off the board by white and black, in variants that keep them
+    collected: Option<(u8, u8)>,
+    analysis: Vec<String>,
+    clock: Option<super::super::clock::Clock>,
+}
+
+impl Terminal {
+    // The animation is the time a falling token takes to go down each row
+    pub fn new(
+        theme: super::Theme,
+        animation: Option<std::time::Duration>,
+    ) -> std::io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        if let Err(e) = execute!(
+            std::io::stdout(),
+            terminal::EnterAlternateScreen,
+            event::EnableMouseCapture,
+            cursor::Hide
+        ) {
+            restore();
+            return Err(e);
+        }
+
+        // Panic messages would otherwise be lost in the alternate screen
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore();
+            hook(info);
+        }));
+
+        Ok(Self {
+            screen: Screen::new(theme),
+            column: None,
+            row: None,
+            animation,
+        })
+    }
+
+    pub fn draw<Game: super::super::game::Game>(&mut self, game: &Game) {
+        self.screen.set(game);
+        self.screen.cursor = None;
+        self.screen.hover = None;
+        let _ = self.screen.render();
+    }
+
+    pub fn select<Game: super::super::game::Game>(
+        &mut self,
+        game: &Game,
+        token: super::super::game::Token,
+    ) -> super::super::player::Result {
+        use super::super::game::Move;
+        use super::super::player::Result;
+        use event::{Event, KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind};
+
+        let size = game.size();
+        let height = game.height();
+        let mut column = self.column.unwrap_or(size / 2).min(size - 1);
+        let pops = (0..size).any(|x| game.allows(token, Move::Pop(x)));
+        // Without gravity, the cursor points at a cell
+        let mut row = (!game.gravity()).then(|| self.row.unwrap_or(height / 2).min(height - 1));
+        self.screen.status = if row.is_some() {
+            format!(
+                "Select the cell for {} [arrows or h/j/k/l, Enter or click to place, q to quit]",
+                self.screen.theme.label(token)
+            )
+        } else {
+            format!(
+                "Select the column for {} [arrows or h/l, Enter or click to drop, {}q to quit]",
+                self.screen.theme.label(token),
+                if pops { "p to pop, " } else { "" }
+            )
+        };
+        let mut pop = false;
+
+        loop {
+            self.screen.cursor = Some(column);
+            self.screen.row = row;
+            let _ = self.screen.render();
+
+            // With a clock, the screen keeps up with the time and the turn ends when it runs out
+            if let Some(clock) = &self.screen.clock {
+                if clock.expired() {
+                    return Result::Repeat;
+                }
+                if !event::poll(std::time::Duration::from_millis(100)).unwrap_or(false) {
+                    continue;
+                }
+            }
+
+            let key = match event::read() {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => key,
+                Ok(Event::Mouse(mouse)) => {
+                    self.screen.hover = self.screen.column_at(mouse.column, mouse.row);
+                    if let Some(hovered) = self.screen.hover {
+                        column = hovered;
+                        // A cell is only picked over the board itself
+                        let pointed = self.screen.row_at(mouse.row);
+                        row = row.map(|row| pointed.unwrap_or(row));
+                        if mouse.kind == MouseEventKind::Down(MouseButton::Left)
+                            && (row.is_none() || pointed.is_some())
+                        {
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                Ok(_) => continue,
+                Err(e) => return Result::Error(e.to_string()),
+            };
+
+            self.screen.hover = None;
+            match key.code {
+                KeyCode::Left | KeyCode::Char('h') => column = column.saturating_sub(1),
+                KeyCode::Right | KeyCode::Char('l') => column = (column + 1).min(size - 1),
+                KeyCode::Up | KeyCode::Char('k') if row.is_some() => {
+                    row = row.map(|row| row.saturating_sub(1));
+                }
+                KeyCode::Down | KeyCode::Char('j') if row.is_some() => {
+                    row = row.map(|row| (row + 1).min(height - 1));
+                }
+                KeyCode::Home => column = 0,
+                KeyCode::End => column = size - 1,
+                KeyCode::Enter | KeyCode::Char(' ') => break,
+                KeyCode::Char('p') if pops => {
+                    pop = true;
+                    break;
+                }
+                KeyCode::Char(c @ '1'..='9') => {
+                    let selected = c as u8 - b'1';
+                    if selected < size {
+                        column = selected;
+                        if row.is_none() {
+                            break;
+                        }
+                    }
+                }
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Result::Quit
+                }
+                KeyCode::Char('q') | KeyCode::Esc => return Result::Quit,
+                _ => {}
+            }
+        }
+
+        self.column = Some(column);
+        self.screen.cursor = None;
+        self.screen.row = None;
+        self.screen.hover = None;
+        Result::Ok(match row {
+            Some(row) => {
+                self.row = Some(row);
+                Move::Place(column, row)
+            }
+            None if pop => Move::Pop(column),
+            None => Move::Drop(column),
+        })
+    }
+
+    pub fn think(
+        &mut self,
+        token: super::super::game::Token,
+        search: &super::super::ai::Search,
+        play: impl FnOnce() -> super::super::player::Result,
+    ) -> super::super::player::Result {
+        let status = format!(
+            "{} is thinking [any key to play now]",
+            self.screen.theme.label(token)
+        );
+        self.watching(status, search, play)
+    }
+
+    pub fn wait(
+        &mut self,
+        token: super::super::game::Token,
+        game: &str,
+        search: &super::super::ai::Search,
+        play: impl FnOnce() -> super::super::player::Result,
+    ) -> super::super::player::Result {
+        let status = format!(
+            "Waiting for {} to play in game {game} [any key to quit]",
+            self.screen.theme.label(token)
+        );
+        self.watching(status, search, play)
+    }
+
+    // Plays while a key press can stop the search
+    fn watching(
+        &mut self,
+        status: String,
+        search: &super::super::ai::Search,
+        play: impl FnOnce() -> super::super::player::Result,
+    ) -> super::super::player::Result {
+        self.screen.cursor = None;
+        self.screen.status = status;
+        let _ = self.screen.render();
+
+        let done = std::sync::atomic::AtomicBool::new(false);
+        let screen = &self.screen;
+        std::thread::scope(|scope| {
+            scope.spawn(|| Self::watch(screen, search, &done));
+            let result = play();
+            done.store(true, std::sync::atomic::Ordering::Relaxed);
+            result
+        })
+    }
+
+    pub fn label(&self, token: super::super::game::Token) -> String {
+        self.screen.theme.label(token)
+    }
+
+    pub fn analysis(&mut self, analysis: Vec<String>) {
+        self.screen.analysis = analysis;
+    }
+
+    pub fn clock(&mut self, clock: &super::super::clock::Clock) {
+        self.screen.clock = Some(clock.clone());
+    }
+
+    pub fn error(&mut self, message: String) {
+        self.screen.error = Some(message);
+    }
+
+    // Pops and placed tokens are not animated, the tokens above a pop simply moving down
+    pub fn record<Game: super::super::game::Game>(
+        &mut self,
+        game: &Game,
+        token: super::super::game::Token,
+        action: super::super::game::Move,
+    ) {
+        self.screen.history.push((token, action));
+        self.screen.error = None;
+        self.screen.last = None;
+
+        let x = match action {
+            super::super::game::Move::Drop(x) => x,
+            super::super::game::Move::Place(x, y) => {
+                self.screen.last = Some((x, y));
+                return;
+            }
+            super::super::game::Move::Pop(_) => return,
+        };
+        if let Ok(y) = game.landing(x) {
+            if let Some(delay) = self.animation {
+                for row in 0..=y {
+                    self.screen.falling = Some((token, x, row));
+                    let _ = self.screen.render();
+                    std::thread::sleep(delay);
+                }
+                self.screen.falling = None;
+            }
+            self.screen.last = Some((x, y));
+        }
+    }
+
+    pub fn finish<Game: super::super::game::Game>(&mut self, game: &Game, message: &str) {
+        self.screen.set(game);
+        self.screen.cursor = None;
+        self.screen.error = None;
+        self.screen.status = format!("{message} [any key]");
+
+        loop {
+            let _ = self.screen.render();
+            match event::read() {
+                Ok(event::Event::Key(key)) if key.kind == event::KeyEventKind::Press => break,
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.column = None;
+        self.row = None;
+        self.screen.last = None;
+        self.screen.error = None;
+        self.screen.history.clear();
+        self.screen.analysis.clear();
+    }
+
+    // Lets a key press stop the search while keeping up with resizes
+    fn watch(
+        screen: &Screen,
+        search: &super::super::ai::Search,
+        done: &std::sync::atomic::AtomicBool,
+    ) {
+        let mut shown = screen.clocks();
+        while !done.load(std::sync::atomic::Ordering::Relaxed) {
+            if !event::poll(std::time::Duration::from_millis(50)).unwrap_or(false) {
+                let clocks = screen.clocks();
+                if clocks != shown {
+                    let _ = screen.render();
+                    shown = clocks;
+                }
+                continue;
+            }
+
+            match event::read() {
+                Ok(event::Event::Key(key)) if key.kind == event::KeyEventKind::Press => {
+                    search.stop();
+                }
+                Ok(event::Event::Resize(..)) => {
+                    let _ = screen.render();
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+fn restore() {
+    let _ = execute!(
+        std::io::stdout(),
+        cursor::Show,
+        event::DisableMouseCapture,
+        terminal::LeaveAlternateScreen
+    );
+    let _ = terminal::disable_raw_mode();
+}
+
+impl Screen {
+    const MARGIN: u16 = 2;
+    const PANE_WIDTH: u16 = 24;
+
+    fn new(theme: super::Theme) -> Self {
+        Self {
+            theme,
+            cells: Vec::new(),
+            size: 0,
+            height: 0,
+            win: 0,
+            gravity: true,
+            cursor: None,
+            row: None,
+            hover: None,
+            last: None,
+            falling: None,
+            status: String::new(),
+            error: None,
+            history: Vec::new(),
+            collected: None,
+            analysis: Vec::new(),
+            clock: None,
+        }
+    }
+
+    fn set<Game: super::super::game::Game>(&mut self, game: &Game) {
+        self.size = game.size();
+        self.height = game.height();
+        self.win = game.win();
+        self.gravity = game.gravity();
+        self.cells = (0..game.height())
+            .map(|y| (0..game.size()).map(|x| game.cell(x, y)).collect())
+            .collect();
+        self.collected = game
+            .collected(super::super::game::Token::White)
+            .zip(game.collected(super::super::game::Token::Black));
+    }
+
+    fn render(&self) -> std::io::Result<()> {
+        use std::io::Write;
+
+        let mut out = std::io::stdout().lock();
+        let (columns, rows) = terminal::size()?;
+        queue!(out, terminal::Clear(terminal::ClearType::All))?;
+
+        // Cells, separator and numbering, each cell taking a border and two characters, and
+        // the rows numbered on the right without gravity
+        let board_width = u16::from(self.size) * 3 + 1 + if self.gravity { 0 } else { 4 };
+        let board_height = u16::from(self.height) + 2;
+
+        // Title, cursor, board, blank, status and error
+        let needed_rows = board_height + 5;
+        let needed_columns = board_width + Self::MARGIN * 2;
+        if rows < needed_rows || columns < needed_columns {
+            let message = format!("Terminal too small, need {needed_columns}x{needed_rows}");
+            put(&mut out, 0, 0, &message, columns)?;
+            return out.flush();
+        }
+
+        let width = columns - Self::MARGIN;
+        queue!(out, style::SetAttribute(style::Attribute::Bold))?;
+        let title = format!("Connect {}", self.win);
+        put(&mut out, Self::MARGIN, 0, &title, width)?;
+        queue!(out, style::SetAttribute(style::Attribute::Reset))?;
+
+        // The clock of the player to move is in bold
+        if let Some(clock) = &self.clock {
+            use super::super::game::Token;
+            let mut x = Self::MARGIN + 12;
+            for token in [Token::White, Token::Black] {
+                let text = format!("{} {}", self.theme.label(token), clock.format(token));
+                if clock.running() == Some(token) {
+                    queue!(out, style::SetAttribute(style::Attribute::Bold))?;
+                }
+                put(&mut out, x, 0, &text, columns.saturating_sub(x))?;
+                queue!(out, style::SetAttribute(style::Attribute::Reset))?;
+                x += u16::try_from(text.chars().count()).unwrap_or(u16::MAX) + 2;
+            }
+        }
+
+        if let Some(column) = self.cursor {
+            let x = Self::MARGIN + 1 + u16::from(column) * 3;
+            put(&mut out, x, 1, self.theme.cursor(), width)?;
+        }
+
+        self.board(&mut out, 2)?;
+
+        let y = board_height + 3;
+        if let Some((white, black)) = self.collected {
+            use super::super::game::Token;
+            let collected = format!(
+                "Collected: {} {white}  {} {black}",
+                self.theme.label(Token::White),
+                self.theme.label(Token::Black)
+            );
+            put(&mut out, Self::MARGIN, y - 1, &collected, width)?;
+        }
+        put(&mut out, Self::MARGIN, y, &self.status, width)?;
+        if let Some(message) = &self.error {
+            queue!(out, style::SetForegroundColor(style::Color::Red))?;
+            put(
+                &mut out,
+                Self::MARGIN,
+                y + 1,
+                &format!("Error: {message}"),
+                width,
+            )?;
+            queue!(out, style::ResetColor)?;
+        }
+
+        // Panes go to the side of the board if they fit, otherwise below it
+        let side = Self::MARGIN * 2 + board_width;
+        if columns >= side + Self::PANE_WIDTH {
+            let height = rows - 1;
+            let split = height / 2;
+            self.panes(&mut out, side, 1, columns - side, split, height - split)?;
+        } else {
+            let top = y + 3;
+            let height = rows.saturating_sub(top);
+            let split = height / 2;
+            self.panes(&mut out, Self::MARGIN, top, width, split, height - split)?;
+        }
+
+        out.flush()
+    }
+
+    // Remaining times as shown, to tell when they need drawing again
+    fn clocks(&self) -> Option<(String, String)> {
+        use super::super::game::Token;
+        self.clock
+            .as_ref()
+            .map(|clock| (clock.format(Token::White), clock.format(Token::Black)))
+    }
+
+    fn panes(
+        &self,
+        out: &mut impl std::io::Write,
+        x: u16,
+        y: u16,
+        width: u16,
+        history_height: u16,
+        analysis_height: u16,
+    ) -> std::io::Result<()> {
+        let history = self
+            .history
+            .chunks(2)
+            .enumerate()
+            .map(|(i, moves)| {
+                let moves = moves
+                    .iter()
+                    .map(|(token, action)| format!("{} {action}", self.theme.label(*token)))
+                    .collect::<Vec<_>>()
+                    .join("  ");
+                format!("{:>3}. {moves}", i + 1)
+            })
+            .collect::<Vec<_>>();
+
+        pane(out, x, y, width, history_height, "History", &history)?;
+        pane(
+            out,
+            x,
+            y + history_height,
+            width,
+            analysis_height,
+            "Analysis",
+            &self.analysis,
+        )
+    }
+
+    // Laid out like the Display of the game, highlighting the column under the pointer
+    // and the last move
+    fn board(&self, out: &mut impl std::io::Write, top: u16) -> std::io::Result<()> {
+        use style::{Attribute, Color, Print, SetAttribute, SetBackgroundColor};
+
+        for (y, row) in (0..).zip(&self.cells) {
+            queue!(out, cursor::MoveTo(Self::MARGIN, top + u16::from(y)))?;
+            for (x, cell) in (0..).zip(row) {
+                queue!(out, Print("|"))?;
+
+                let pointed = match self.row {
+                    Some(row) => self.cursor == Some(x) && row == y,
+                    None => self.hover == Some(x),
+                };
+                if pointed {
+                    queue!(out, SetAttribute(Attribute::Reverse))?;
+                }
+                if self.last == Some((x, y)) {
+                    queue!(out, SetBackgroundColor(Color::DarkGrey))?;
+                }
+
+                let token = match self.falling {
+                    Some((token, column, row)) if (column, row) == (x, y) => Some(token),
+                    _ => *cell,
+                };
+                match token {
+                    Some(token) => {
+                        if let Some(colour) = self.theme.colour(token) {
+                            queue!(out, style::SetForegroundColor(colour))?;
+                        }
+                        queue!(out, Print(self.theme.glyph(token)))?;
+                    }
+                    None => queue!(out, Print("  "))?,
+                }
+                queue!(out, SetAttribute(Attribute::Reset), style::ResetColor)?;
+            }
+            queue!(out, Print("|"))?;
+            if !self.gravity {
+                queue!(out, Print(format!(" {}", y + 1)))?;
+            }
+        }
+
+        let separator = top + u16::from(self.height);
+        let line = "-".repeat(usize::from(self.size) * 3 + 1);
+        queue!(out, cursor::MoveTo(Self::MARGIN, separator), Print(line))?;
+
+        queue!(out, cursor::MoveTo(Self::MARGIN, separator + 1))?;
+        for x in 0..self.size {
+            queue!(out, Print(" "))?;
+            if self.hover == Some(x) {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(
+                out,
+                Print(format!("{:2}", x + 1)),
+                SetAttribute(Attribute::Reset)
+            )?;
+        }
+        Ok(())
+    }
+
+    // Row of the board under a position
+    fn row_at(&self, y: u16) -> Option<u8> {
+        let row = u8::try_from(y.checked_sub(2)?).ok()?;
+        (row < self.height).then_some(row)
+    }
+
+    // Column under a position anywhere from the cursor down to the numbering below the board
+    fn column_at(&self, x: u16, y: u16) -> Option<u8> {
+        let column = u8::try_from(x.checked_sub(Self::MARGIN)? / 3).ok()?;
+        if (1..4 + u16::from(self.height)).contains(&y) && column < self.size {
+            Some(column)
+        } else {
+            None
+        }
+    }
+}
+
+// Titled list of lines, showing the most recent ones when they do not fit
+fn pane(
+    out: &mut impl std::io::Write,
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+    title: &str,
+    lines: &[String],
+) -> std::io::Result<()> {
+    if height < 2 {
+        return Ok(());
+    }
+
+    queue!(out, style::SetAttribute(style::Attribute::Bold))?;
+    put(out, x, y, title, width)?;
+    queue!(out, style::SetAttribute(style::Attribute::Reset))?;
+
+    let visible = usize::from(height - 1);
+    let skip = lines.len().saturating_sub(visible);
+    for (row, line) in (y + 1..).zip(lines.iter().skip(skip)) {
+        put(out, x, row, line, width)?;
+    }
+    Ok(())
+}
+
+fn put(
+    out: &mut impl std::io::Write,
+    x: u16,
+    y: u16,
+    text: &str,
+    width: u16,
+) -> std::io::Result<()> {
+    let text = text.chars().take(usize::from(width)).collect::<String>();
+    queue!(out, cursor::MoveTo(x, y), style::Print(text))
+}
+
+#[cfg(test)]
+mod tests {
+    mod screen {
+        use super::super::*;
+
+        #[test]
+        fn column_at() {
+            let mut screen = Screen::new(super::super::super::Theme::Shade);
+            screen.size = 2;
+            screen.height = 2;
+
+            assert_eq!(screen.column_at(2, 1), Some(0));
+            assert_eq!(screen.column_at(4, 2), Some(0));
+            assert_eq!(screen.column_at(5, 3), Some(1));
+            assert_eq!(screen.column_at(7, 2), Some(1));
+            assert_eq!(screen.column_at(8, 2), None);
+            assert_eq!(screen.column_at(1, 2), None);
+            assert_eq!(screen.column_at(3, 0), None);
+            assert_eq!(screen.column_at(3, 5), Some(0));
+            assert_eq!(screen.column_at(3, 6), None);
+        }
+
+        #[test]
+        fn row_at() {
+            let mut screen = Screen::new(super::super::super::Theme::Shade);
+            screen.size = 2;
+            screen.height = 2;
+
+            assert_eq!(screen.row_at(1), None);
+            assert_eq!(screen.row_at(2), Some(0));
+            assert_eq!(screen.row_at(3), Some(1));
+            assert_eq!(screen.row_at(4), None);
+        }
+    }
+}
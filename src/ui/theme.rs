@@ -0,0 +1,188 @@
+use crossterm::style::{Color, Stylize};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Theme {
+    Shade,
+    Colour,
+    Ascii,
+    Contrast,
+}
+
+impl Theme {
+    // Colours unless disabled by NO_COLOR or not writing to a terminal, and
+    // no drawing characters unless the locale is UTF-8
+    pub fn detect() -> Self {
+        use std::io::IsTerminal;
+        if !Self::unicode() {
+            Self::Ascii
+        } else if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+            || !std::io::stdout().is_terminal()
+        {
+            Self::Shade
+        } else {
+            Self::Colour
+        }
+    }
+
+    fn unicode() -> bool {
+        ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .map_or(cfg!(windows), |locale| {
+                let locale = locale.to_lowercase();
+                locale.contains("utf-8") || locale.contains("utf8")
+            })
+    }
+
+    // Contents of a cell holding the token, always two characters wide
+    pub fn glyph(self, token: super::super::game::Token) -> &'static str {
+        use super::super::game::Token;
+        match (self, token) {
+            (Self::Shade, Token::White) => "\u{2593}\u{2593}",
+            (Self::Shade, Token::Black) => "\u{2591}\u{2591}",
+            (Self::Colour, _) | (Self::Contrast, Token::White) => "\u{2588}\u{2588}",
+            (Self::Ascii, Token::White) => "XX",
+            (Self::Ascii, Token::Black) => "OO",
+            (Self::Contrast, Token::Black) => "[]",
+        }
+    }
+
+    pub fn colour(self, token: super::super::game::Token) -> Option<Color> {
+        use super::super::game::Token;
+        match (self, token) {
+            (Self::Colour, Token::White) => Some(Color::Red),
+            (Self::Colour, Token::Black) => Some(Color::Yellow),
+            (Self::Contrast, _) => Some(Color::White),
+            (Self::Shade | Self::Ascii, _) => None,
+        }
+    }
+
+    // Name of the token in a line of text
+    pub fn label(self, token: super::super::game::Token) -> String {
+        use super::super::game::Token;
+        match (self, token) {
+            (Self::Colour, Token::White) => String::from("Red"),
+            (Self::Colour, Token::Black) => String::from("Yellow"),
+            (Self::Ascii, Token::White) => String::from("X"),
+            (Self::Ascii, Token::Black) => String::from("O"),
+            (Self::Shade | Self::Contrast, _) => String::from(self.glyph(token)),
+        }
+    }
+
+    // The glyph of the token, coloured with escape codes if the theme has colours
+    pub fn token(self, token: super::super::game::Token) -> String {
+        let glyph = self.glyph(token);
+        match self.colour(token) {
+            Some(colour) if self == Self::Contrast => glyph.with(colour).bold().to_string(),
+            Some(colour) => glyph.with(colour).to_string(),
+            None => String::from(glyph),
+        }
+    }
+
+    pub fn cursor(self) -> &'static str {
+        if self == Self::Ascii {
+            "vv"
+        } else {
+            "\u{25bc}\u{25bc}"
+        }
+    }
+
+    // The board laid out like the Display of the game, with the tokens drawn by the theme
+    pub fn board<Game: super::super::game::Game>(self, game: &Game) -> String {
+        use super::super::game::Token;
+        use std::fmt::Write;
+        let mut board = String::new();
+
+        for y in 0..game.height() {
+            for x in 0..game.size() {
+                let _ = match game.cell(x, y) {
+                    Some(token) => write!(board, "|{}", self.token(token)),
+                    None => write!(board, "|  "),
+                };
+            }
+            board.push('|');
+            if !game.gravity() {
+                let _ = write!(board, " {}", y + 1);
+            }
+            board.push('\n');
+        }
+
+        for _ in 0..game.size() {
+            board.push_str("---");
+        }
+        board.push_str("-\n");
+
+        for i in 0..game.size() {
+            let _ = write!(board, " {:2}", i + 1);
+        }
+        board.push('\n');
+
+        if let (Some(white), Some(black)) =
+            (game.collected(Token::White), game.collected(Token::Black))
+        {
+            let _ = writeln!(
+                board,
+                "Collected: {} {white}  {} {black}",
+                self.token(Token::White),
+                self.token(Token::Black)
+            );
+        }
+        board
+    }
+}
+
+impl std::str::FromStr for Theme {
+    type Err = ();
+
+    fn from_str(string: &str) -> std::result::Result<Self, Self::Err> {
+        match string {
+            "shade" => Ok(Self::Shade),
+            "colour" | "color" => Ok(Self::Colour),
+            "ascii" => Ok(Self::Ascii),
+            "contrast" => Ok(Self::Contrast),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Theme {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Shade => write!(fmt, "shade"),
+            Self::Colour => write!(fmt, "colour"),
+            Self::Ascii => write!(fmt, "ascii"),
+            Self::Contrast => write!(fmt, "contrast"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod theme {
+        use super::super::*;
+        use crate::game::{self, Game, Token};
+
+        #[test]
+        fn board() {
+            let game = game::new(7, 6, 4).place(Token::White, 2).unwrap();
+            let board = Theme::Ascii.board(&game);
+            let lines = board.lines().collect::<Vec<_>>();
+
+            assert_eq!(lines.len(), 8);
+            assert_eq!(lines[5], "|  |  |XX|  |  |  |  |");
+            assert_eq!(lines[6], "----------------------");
+            assert_eq!(lines[7], "  1  2  3  4  5  6  7");
+            assert_eq!(board, game.to_string().replace("\u{2593}", "X"));
+        }
+
+        #[test]
+        fn glyph_width() {
+            for theme in &[Theme::Shade, Theme::Colour, Theme::Ascii, Theme::Contrast] {
+                for token in &[Token::White, Token::Black] {
+                    assert_eq!(theme.glyph(*token).chars().count(), 2);
+                }
+            }
+        }
+    }
+}
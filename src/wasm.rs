@@ -0,0 +1,90 @@
+// The game and the AI for web pages, through wasm-bindgen. Columns count from 0, as they do in
+// the game, and the position is changed in place:
+//
+//   const game = new_game(7, 6, 4);
+//   place(game, best_move(game, 6));
+//   if (status(game) === "ongoing") { ... }
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub struct Game {
+    game: super::game::Connect4,
+    token: super::game::Token,
+}
+
+#[wasm_bindgen]
+pub fn new_game(width: u8, height: u8, win: u8) -> Result<Game, JsError> {
+    let sides = 1..=127;
+    let longest = width.max(height);
+    if !sides.contains(&width) || !sides.contains(&height) || win < 2 || win > longest {
+        return Err(JsError::new(&format!(
+            "a board of {width} by {height} cannot have {win} in a row"
+        )));
+    }
+
+    Ok(Game {
+        game: super::game::new(width, height, win),
+        token: super::game::Token::White,
+    })
+}
+
+// Drops a token of the player to move
+#[wasm_bindgen]
+pub fn place(game: &mut Game, column: u8) -> Result<(), JsError> {
+    use super::game::Game;
+    if game.game.status() != super::game::Status::Ongoing {
+        return Err(JsError::new(&super::game::Error::Over.to_string()));
+    }
+    game.game = game
+        .game
+        .place(game.token, column)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    if game.game.status() == super::game::Status::Ongoing {
+        game.token = !game.token;
+    }
+    Ok(())
+}
+
+// ongoing, white or black once one of them won, or tie
+#[wasm_bindgen]
+pub fn status(game: &Game) -> String {
+    use super::game::Game;
+    String::from(match game.game.status() {
+        super::game::Status::Victory => colour(game.token),
+        super::game::Status::Defeat => colour(!game.token),
+        super::game::Status::Tie => "tie",
+        super::game::Status::Ongoing | super::game::Status::Timeout => "ongoing",
+    })
+}
+
+// white or black, to play next
+#[wasm_bindgen]
+pub fn turn(game: &Game) -> String {
+    String::from(colour(game.token))
+}
+
+// For the player to move, searching to the given depth in the page, as the AI has no threads
+// there
+#[wasm_bindgen]
+pub fn best_move(game: &Game, depth: u8) -> Result<u8, JsError> {
+    use super::game::Game;
+    if game.game.status() != super::game::Status::Ongoing {
+        return Err(JsError::new(&super::game::Error::Over.to_string()));
+    }
+    Ok(super::ai::Ai::new(depth)
+        .best_move(&game.game, game.token)
+        .column())
+}
+
+// As the game is drawn in the terminal, for a <pre>
+#[wasm_bindgen]
+pub fn render(game: &Game) -> String {
+    game.game.to_string()
+}
+
+fn colour(token: super::game::Token) -> &'static str {
+    match token {
+        super::game::Token::White => "white",
+        super::game::Token::Black => "black",
+    }
+}